@@ -0,0 +1,168 @@
+// Error returned by every Tauri command. It serialises as {"kind": "...", "message": "...", ...}
+// with any extra fields of the variant, so the frontend can branch on `kind` and still show `message`.
+use crate::helix::HelixError;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeychatError {
+    NotConnected(String),
+    Unauthorized(String),
+    MissingScope(String), // The OAuth scope the login lacks
+    RateLimited { retry_after: Option<Duration> },
+    NotFound(String),
+    PlatformError { status: u16, body: String },
+    Network(String),
+    Other(String), // Local failures, e.g. the browser couldn't be opened
+}
+
+impl HeychatError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            HeychatError::NotConnected(_) => "not_connected",
+            HeychatError::Unauthorized(_) => "unauthorized",
+            HeychatError::MissingScope(_) => "missing_scope",
+            HeychatError::RateLimited { .. } => "rate_limited",
+            HeychatError::NotFound(_) => "not_found",
+            HeychatError::PlatformError { .. } => "platform_error",
+            HeychatError::Network(_) => "network",
+            HeychatError::Other(_) => "other",
+        }
+    }
+
+    // Classifies a failed platform response by status, keeping the body for the message
+    pub async fn from_response(res: reqwest::Response) -> Self {
+        let status = res.status();
+        let retry_after = res.headers().get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok()?.parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let body = res.text().await.unwrap_or_default();
+        match status {
+            reqwest::StatusCode::UNAUTHORIZED => HeychatError::Unauthorized(body),
+            reqwest::StatusCode::NOT_FOUND => HeychatError::NotFound(body),
+            reqwest::StatusCode::TOO_MANY_REQUESTS => HeychatError::RateLimited { retry_after },
+            // Google APIs report quota and rate limits as 403 with a reason in the body
+            reqwest::StatusCode::FORBIDDEN if body.contains("\"quotaExceeded\"") || body.contains("\"rateLimitExceeded\"") => {
+                HeychatError::RateLimited { retry_after }
+            }
+            _ => HeychatError::PlatformError { status: status.as_u16(), body },
+        }
+    }
+}
+
+impl std::fmt::Display for HeychatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeychatError::NotConnected(msg)
+            | HeychatError::Unauthorized(msg)
+            | HeychatError::NotFound(msg)
+            | HeychatError::Other(msg) => write!(f, "{}", msg),
+            HeychatError::MissingScope(scope) => write!(f, "Login is missing the {} permission; please log in again", scope),
+            HeychatError::RateLimited { retry_after: Some(wait) } => write!(f, "Rate limited, try again in {}s", wait.as_secs().max(1)),
+            HeychatError::RateLimited { retry_after: None } => write!(f, "Rate limited, try again later"),
+            HeychatError::PlatformError { status, body } => write!(f, "Request failed ({}): {}", status, body),
+            HeychatError::Network(msg) => write!(f, "Network error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for HeychatError {}
+
+impl Serialize for HeychatError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("kind", self.kind())?;
+        map.serialize_entry("message", &self.to_string())?;
+        match self {
+            HeychatError::MissingScope(scope) => map.serialize_entry("scope", scope)?,
+            HeychatError::RateLimited { retry_after } => {
+                map.serialize_entry("retry_after", &retry_after.map(|d| d.as_secs()))?
+            }
+            HeychatError::PlatformError { status, body } => {
+                map.serialize_entry("status", status)?;
+                map.serialize_entry("body", body)?;
+            }
+            _ => {}
+        }
+        map.end()
+    }
+}
+
+impl From<reqwest::Error> for HeychatError {
+    fn from(e: reqwest::Error) -> Self {
+        match e.status() {
+            Some(status) => HeychatError::PlatformError { status: status.as_u16(), body: e.to_string() },
+            None if e.is_decode() => HeychatError::Other(format!("Unexpected response: {}", e)),
+            None => HeychatError::Network(e.to_string()),
+        }
+    }
+}
+
+impl From<HelixError> for HeychatError {
+    fn from(e: HelixError) -> Self {
+        match e {
+            HelixError::Request(msg) => HeychatError::Network(msg),
+            HelixError::Status { status: 401, message } => HeychatError::Unauthorized(message),
+            HelixError::Status { status: 404, message } => HeychatError::NotFound(message),
+            HelixError::Status { status, message } => HeychatError::PlatformError { status, body: message },
+            HelixError::RateLimited { retry_after } => HeychatError::RateLimited { retry_after: Some(retry_after) },
+            HelixError::Decode(msg) => HeychatError::Other(format!("Unexpected Helix response: {}", msg)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn wire(err: HeychatError) -> serde_json::Value {
+        serde_json::to_value(err).unwrap()
+    }
+
+    #[test]
+    fn serialises_rate_limited_with_retry_after() {
+        assert_eq!(
+            wire(HeychatError::RateLimited { retry_after: Some(Duration::from_secs(30)) }),
+            json!({ "kind": "rate_limited", "message": "Rate limited, try again in 30s", "retry_after": 30 })
+        );
+        assert_eq!(
+            wire(HeychatError::RateLimited { retry_after: None }),
+            json!({ "kind": "rate_limited", "message": "Rate limited, try again later", "retry_after": null })
+        );
+    }
+
+    #[test]
+    fn serialises_platform_error_with_status_and_body() {
+        assert_eq!(
+            wire(HeychatError::PlatformError { status: 500, body: "oops".to_string() }),
+            json!({ "kind": "platform_error", "message": "Request failed (500): oops", "status": 500, "body": "oops" })
+        );
+    }
+
+    #[test]
+    fn serialises_missing_scope_with_scope() {
+        assert_eq!(
+            wire(HeychatError::MissingScope("moderator:manage:banned_users".to_string())),
+            json!({
+                "kind": "missing_scope",
+                "message": "Login is missing the moderator:manage:banned_users permission; please log in again",
+                "scope": "moderator:manage:banned_users"
+            })
+        );
+    }
+
+    #[test]
+    fn serialises_message_only_variants() {
+        let cases = [
+            (HeychatError::NotConnected("Not in that channel".to_string()), "not_connected", "Not in that channel"),
+            (HeychatError::Unauthorized("Token expired".to_string()), "unauthorized", "Token expired"),
+            (HeychatError::NotFound("No such user".to_string()), "not_found", "No such user"),
+            (HeychatError::Network("timed out".to_string()), "network", "Network error: timed out"),
+            (HeychatError::Other("Couldn't open browser".to_string()), "other", "Couldn't open browser"),
+        ];
+        for (err, kind, message) in cases {
+            assert_eq!(wire(err), json!({ "kind": kind, "message": message }));
+        }
+    }
+}
@@ -0,0 +1,451 @@
+// Typed client for the Twitch Helix API (https://dev.twitch.tv/docs/api/reference).
+// Requests share one rate-limit bucket, kept in sync with the Ratelimit-* response headers,
+// and a 429 is retried once the bucket resets.
+use reqwest::header::HeaderMap;
+use reqwest::{Method, StatusCode};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const HELIX_BASE_URL: &str = "https://api.twitch.tv/helix";
+
+// How often a rate-limited request is retried before giving up
+const HELIX_MAX_RETRIES: u32 = 3;
+// Wait used when a 429 doesn't say when the bucket resets
+const HELIX_DEFAULT_RETRY: Duration = Duration::from_secs(1);
+
+// Token and Client-Id for a request, taken from the validated user token
+#[derive(Debug, Clone)]
+pub struct HelixAuth {
+    pub token: String,
+    pub client_id: String,
+}
+
+#[derive(Debug)]
+pub enum HelixError {
+    Request(String),
+    Status { status: u16, message: String },
+    RateLimited { retry_after: Duration },
+    Decode(String),
+}
+
+impl std::fmt::Display for HelixError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HelixError::Request(e) => write!(f, "Helix request failed: {}", e),
+            HelixError::Status { status, message } => write!(f, "Helix returned {}: {}", status, message),
+            HelixError::RateLimited { retry_after } => write!(f, "Helix rate limit hit, retry in {}s", retry_after.as_secs()),
+            HelixError::Decode(e) => write!(f, "Unexpected Helix response: {}", e),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct HelixResponse<T> {
+    pub data: Vec<T>,
+    #[serde(default)]
+    pub pagination: Option<Pagination>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct Pagination {
+    pub cursor: Option<String>,
+}
+
+// Error body, e.g. {"error":"Unauthorized","status":401,"message":"Invalid OAuth token"}
+#[derive(Deserialize, Debug)]
+struct HelixErrorBody {
+    message: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct User {
+    pub id: String,
+    pub login: String,
+    pub display_name: String,
+    pub profile_image_url: Option<String>,
+    pub created_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Emote {
+    pub id: String,
+    pub name: String,
+    pub emote_type: Option<String>,
+    pub emote_set_id: Option<String>,
+    pub owner_id: Option<String>,
+    #[serde(default)]
+    pub format: Vec<String>,
+    #[serde(default)]
+    pub scale: Vec<String>,
+    #[serde(default)]
+    pub theme_mode: Vec<String>,
+    // Not sent by Helix; filled in by the emote picker command
+    #[serde(default)]
+    pub category: Option<String>, // "Global", "Channel", "User"
+    #[serde(default)]
+    pub locked: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BanUserRequest {
+    pub user_id: String,
+    pub reason: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u32>, // Seconds; None bans permanently
+}
+
+#[derive(Serialize, Debug)]
+pub struct Choice {
+    pub title: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CreatePollRequest {
+    pub broadcaster_id: String,
+    pub title: String,
+    pub choices: Vec<Choice>,
+    pub duration: u32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CreatePredictionRequest {
+    pub broadcaster_id: String,
+    pub title: String,
+    pub outcomes: Vec<Choice>,
+    pub prediction_window: u32,
+}
+
+// Helix rate limiting is a token bucket per client id and user. We mirror it from the
+// Ratelimit-Remaining/Ratelimit-Reset headers and spend tokens locally so that concurrent
+// requests don't all go out on the last one.
+#[derive(Debug, Default)]
+struct RateLimit {
+    limit: Option<u32>,
+    remaining: Option<u32>,
+    reset_at: Option<SystemTime>,
+}
+
+impl RateLimit {
+    // Takes a token, or says how long to wait for the bucket to refill
+    fn acquire(&mut self, now: SystemTime) -> Option<Duration> {
+        match (self.remaining, self.reset_at) {
+            (Some(0), Some(reset_at)) if reset_at > now => return reset_at.duration_since(now).ok(),
+            (Some(0), _) => self.remaining = self.limit,
+            _ => {}
+        }
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining = remaining.saturating_sub(1);
+        }
+        None
+    }
+
+    fn update(&mut self, headers: &HeaderMap) {
+        if let Some(limit) = header_u64(headers, "ratelimit-limit") {
+            self.limit = Some(limit as u32);
+        }
+        if let Some(remaining) = header_u64(headers, "ratelimit-remaining") {
+            self.remaining = Some(remaining as u32);
+        }
+        if let Some(reset) = header_u64(headers, "ratelimit-reset") {
+            self.reset_at = Some(UNIX_EPOCH + Duration::from_secs(reset));
+        }
+    }
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+pub struct HelixClient {
+    http: reqwest::Client,
+    base_url: String,
+    rate_limit: Mutex<RateLimit>,
+}
+
+impl HelixClient {
+    pub fn new(http: reqwest::Client, base_url: impl Into<String>) -> Self {
+        Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            rate_limit: Mutex::new(RateLimit::default()),
+        }
+    }
+
+    // Users by login and/or id (up to 100 of each)
+    pub async fn get_users(&self, auth: &HelixAuth, logins: &[&str], ids: &[&str]) -> Result<Vec<User>, HelixError> {
+        let mut query: Vec<(&str, String)> = logins.iter().map(|l| ("login", l.to_string())).collect();
+        query.extend(ids.iter().map(|id| ("id", id.to_string())));
+        Ok(self.request::<User>(auth, Method::GET, "users", &query, None).await?.data)
+    }
+
+    pub async fn ban_user(&self, auth: &HelixAuth, broadcaster_id: &str, moderator_id: &str, ban: BanUserRequest) -> Result<(), HelixError> {
+        let query = [("broadcaster_id", broadcaster_id.to_string()), ("moderator_id", moderator_id.to_string())];
+        let body = serde_json::json!({ "data": ban });
+        self.request::<IgnoredAny>(auth, Method::POST, "moderation/bans", &query, Some(body)).await?;
+        Ok(())
+    }
+
+    pub async fn create_poll(&self, auth: &HelixAuth, poll: CreatePollRequest) -> Result<(), HelixError> {
+        let body = serde_json::to_value(poll).map_err(|e| HelixError::Decode(e.to_string()))?;
+        self.request::<IgnoredAny>(auth, Method::POST, "polls", &[], Some(body)).await?;
+        Ok(())
+    }
+
+    pub async fn create_prediction(&self, auth: &HelixAuth, prediction: CreatePredictionRequest) -> Result<(), HelixError> {
+        let body = serde_json::to_value(prediction).map_err(|e| HelixError::Decode(e.to_string()))?;
+        self.request::<IgnoredAny>(auth, Method::POST, "predictions", &[], Some(body)).await?;
+        Ok(())
+    }
+
+    // Every emote the user can use, across all pages
+    pub async fn get_user_emotes(&self, auth: &HelixAuth, user_id: &str) -> Result<Vec<Emote>, HelixError> {
+        self.get_all(auth, "chat/emotes/user", &[("user_id", user_id.to_string())]).await
+    }
+
+    pub async fn get_global_emotes(&self, auth: &HelixAuth) -> Result<Vec<Emote>, HelixError> {
+        Ok(self.request::<Emote>(auth, Method::GET, "chat/emotes/global", &[], None).await?.data)
+    }
+
+    pub async fn get_channel_emotes(&self, auth: &HelixAuth, broadcaster_id: &str) -> Result<Vec<Emote>, HelixError> {
+        let query = [("broadcaster_id", broadcaster_id.to_string())];
+        Ok(self.request::<Emote>(auth, Method::GET, "chat/emotes", &query, None).await?.data)
+    }
+
+    // Follows pagination cursors until the last page
+    pub async fn get_all<T: DeserializeOwned>(&self, auth: &HelixAuth, path: &str, query: &[(&str, String)]) -> Result<Vec<T>, HelixError> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut page_query = query.to_vec();
+            if let Some(after) = cursor.take() {
+                page_query.push(("after", after));
+            }
+            let page = self.request::<T>(auth, Method::GET, path, &page_query, None).await?;
+            items.extend(page.data);
+            match page.pagination.and_then(|p| p.cursor).filter(|c| !c.is_empty()) {
+                Some(next) => cursor = Some(next),
+                None => return Ok(items),
+            }
+        }
+    }
+
+    pub async fn request<T: DeserializeOwned>(
+        &self,
+        auth: &HelixAuth,
+        method: Method,
+        path: &str,
+        query: &[(&str, String)],
+        body: Option<serde_json::Value>,
+    ) -> Result<HelixResponse<T>, HelixError> {
+        let url = format!("{}/{}", self.base_url, path.trim_start_matches('/'));
+        let mut retries = 0;
+
+        loop {
+            let wait = self.rate_limit.lock().unwrap().acquire(SystemTime::now());
+            if let Some(wait) = wait {
+                eprintln!("Helix rate limit exhausted, waiting {:?}", wait);
+                tokio::time::sleep(wait).await;
+                continue;
+            }
+
+            let mut req = self.http.request(method.clone(), &url)
+                .header("Authorization", format!("Bearer {}", auth.token))
+                .header("Client-Id", &auth.client_id)
+                .query(query);
+            if let Some(body) = &body {
+                req = req.json(body);
+            }
+            let res = req.send().await.map_err(|e| HelixError::Request(e.to_string()))?;
+
+            let status = res.status();
+            self.rate_limit.lock().unwrap().update(res.headers());
+
+            if status == StatusCode::TOO_MANY_REQUESTS {
+                let retry_after = header_u64(res.headers(), "ratelimit-reset")
+                    .map(|reset| (UNIX_EPOCH + Duration::from_secs(reset)).duration_since(SystemTime::now()).unwrap_or_default())
+                    .unwrap_or(HELIX_DEFAULT_RETRY);
+                if retries >= HELIX_MAX_RETRIES {
+                    return Err(HelixError::RateLimited { retry_after });
+                }
+                retries += 1;
+                eprintln!("Helix 429 on {}, retrying in {:?}", path, retry_after);
+                tokio::time::sleep(retry_after).await;
+                continue;
+            }
+
+            let text = res.text().await.map_err(|e| HelixError::Request(e.to_string()))?;
+            if !status.is_success() {
+                let message = serde_json::from_str::<HelixErrorBody>(&text).map(|b| b.message).unwrap_or(text);
+                return Err(HelixError::Status { status: status.as_u16(), message });
+            }
+            // Some endpoints answer 204 No Content
+            if text.trim().is_empty() {
+                return Ok(HelixResponse { data: Vec::new(), pagination: None });
+            }
+            return serde_json::from_str(&text).map_err(|e| HelixError::Decode(e.to_string()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // Minimal HTTP/1.1 server that answers each connection with the next scripted response
+    // and records the request it got
+    struct MockServer {
+        url: String,
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl MockServer {
+        async fn start(responses: Vec<String>) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let recorded = requests.clone();
+            tokio::spawn(async move {
+                for response in responses {
+                    let (mut socket, _) = listener.accept().await.unwrap();
+                    let mut buf = vec![0u8; 16 * 1024];
+                    let mut request = String::new();
+                    // Read until the end of the headers plus the declared body length
+                    loop {
+                        let n = socket.read(&mut buf).await.unwrap();
+                        request.push_str(&String::from_utf8_lossy(&buf[..n]));
+                        if let Some(end) = request.find("\r\n\r\n") {
+                            let length = request.lines()
+                                .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                                .unwrap_or(0);
+                            if request.len() >= end + 4 + length || n == 0 {
+                                break;
+                            }
+                        }
+                    }
+                    recorded.lock().unwrap().push(request);
+                    socket.write_all(response.as_bytes()).await.unwrap();
+                    socket.shutdown().await.unwrap();
+                }
+            });
+            Self { url, requests }
+        }
+
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    fn response(status: &str, headers: &[(&str, String)], body: &str) -> String {
+        let mut out = format!("HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n", status, body.len());
+        for (name, value) in headers {
+            out.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        out.push_str("\r\n");
+        out.push_str(body);
+        out
+    }
+
+    fn auth() -> HelixAuth {
+        HelixAuth { token: "tok123".to_string(), client_id: "cid456".to_string() }
+    }
+
+    fn now_secs() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+    }
+
+    #[tokio::test]
+    async fn sends_credentials_and_decodes_users() {
+        let server = MockServer::start(vec![response(
+            "200 OK",
+            &[],
+            r#"{"data":[{"id":"141981764","login":"twitchdev","display_name":"TwitchDev","profile_image_url":null,"created_at":"2016-12-14T20:32:28Z"}]}"#,
+        )]).await;
+        let helix = HelixClient::new(reqwest::Client::new(), &server.url);
+
+        let users = helix.get_users(&auth(), &["twitchdev"], &[]).await.unwrap();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].id, "141981764");
+
+        let request = &server.requests()[0];
+        assert!(request.starts_with("GET /users?login=twitchdev HTTP/1.1"));
+        assert!(request.to_ascii_lowercase().contains("authorization: bearer tok123"));
+        assert!(request.to_ascii_lowercase().contains("client-id: cid456"));
+    }
+
+    #[tokio::test]
+    async fn follows_pagination_cursors() {
+        let server = MockServer::start(vec![
+            response("200 OK", &[], r#"{"data":[{"id":"1","name":"Kappa"}],"pagination":{"cursor":"page2"}}"#),
+            response("200 OK", &[], r#"{"data":[{"id":"2","name":"PogChamp"}],"pagination":{}}"#),
+        ]).await;
+        let helix = HelixClient::new(reqwest::Client::new(), &server.url);
+
+        let emotes = helix.get_user_emotes(&auth(), "42").await.unwrap();
+        let names: Vec<_> = emotes.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["Kappa", "PogChamp"]);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].starts_with("GET /chat/emotes/user?user_id=42&after=page2 HTTP/1.1"));
+    }
+
+    #[tokio::test]
+    async fn retries_after_429() {
+        let server = MockServer::start(vec![
+            response("429 Too Many Requests", &[("Ratelimit-Remaining", "0".to_string()), ("Ratelimit-Reset", now_secs().to_string())], r#"{"error":"Too Many Requests","status":429,"message":""}"#),
+            response("200 OK", &[("Ratelimit-Limit", "800".to_string()), ("Ratelimit-Remaining", "799".to_string())], r#"{"data":[]}"#),
+        ]).await;
+        let helix = HelixClient::new(reqwest::Client::new(), &server.url);
+
+        helix.create_poll(&auth(), CreatePollRequest {
+            broadcaster_id: "1".to_string(),
+            title: "Best emote?".to_string(),
+            choices: vec![Choice { title: "Kappa".to_string() }, Choice { title: "PogChamp".to_string() }],
+            duration: 60,
+        }).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].contains(r#""choices":[{"title":"Kappa"},{"title":"PogChamp"}]"#));
+    }
+
+    #[tokio::test]
+    async fn reports_helix_error_message() {
+        let server = MockServer::start(vec![response(
+            "401 Unauthorized",
+            &[],
+            r#"{"error":"Unauthorized","status":401,"message":"Missing scope: moderator:manage:banned_users"}"#,
+        )]).await;
+        let helix = HelixClient::new(reqwest::Client::new(), &server.url);
+
+        let ban = BanUserRequest { user_id: "9".to_string(), reason: "spam".to_string(), duration: None };
+        match helix.ban_user(&auth(), "1", "2", ban).await {
+            Err(HelixError::Status { status, message }) => {
+                assert_eq!(status, 401);
+                assert_eq!(message, "Missing scope: moderator:manage:banned_users");
+            }
+            other => panic!("expected a status error, got {:?}", other),
+        }
+        assert!(!server.requests()[0].contains("duration"));
+    }
+
+    #[test]
+    fn empty_bucket_waits_for_reset() {
+        let now = SystemTime::now();
+        let mut bucket = RateLimit {
+            limit: Some(800),
+            remaining: Some(1),
+            reset_at: Some(now + Duration::from_secs(5)),
+        };
+        assert_eq!(bucket.acquire(now), None);
+        assert_eq!(bucket.acquire(now), Some(Duration::from_secs(5)));
+        // Once the reset time has passed the bucket is full again
+        assert_eq!(bucket.acquire(now + Duration::from_secs(6)), None);
+        assert_eq!(bucket.remaining, Some(799));
+    }
+}
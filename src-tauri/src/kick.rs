@@ -1,13 +1,19 @@
 use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_opener::OpenerExt;
-use crate::models::{ChatMessage, Platform};
+use crate::models::{ChatMessage, ChatSource, Emote, MessageType, ModerationAction, ModerationEvent, Platform};
+use crate::error::HeychatError;
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
 use futures_util::{StreamExt, SinkExt};
 
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
+use regex::Regex;
 use reqwest::header::{USER_AGENT, AUTHORIZATION, CONTENT_TYPE, ACCEPT};
-use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tokio::time::Instant;
 use rand::{Rng, thread_rng};
 use sha2::{Sha256, Digest};
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
@@ -15,15 +21,24 @@ use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
 const PUSHER_KEY: &str = "32cbd69e4b950bf97679";
 const PUSHER_CLUSTER: &str = "us2";
 
+// Subscription changes forwarded to the shared Pusher socket
+#[derive(Debug)]
+pub enum KickCommand {
+    Subscribe(u64),
+    Unsubscribe(u64),
+}
+
 // Store broadcaster user IDs for sending messages
 // Map<ChannelSlug, BroadcasterUserId>
 pub struct KickState {
     pub broadcaster_ids: Arc<Mutex<HashMap<String, u64>>>,
     pub pkce_verifier: Arc<Mutex<Option<String>>>,
     pub shutdown_tx: Arc<Mutex<Option<tokio::sync::broadcast::Sender<()>>>>,
+    pub chatrooms: Arc<Mutex<HashMap<u64, String>>>, // Map<ChatroomId, ChannelSlug>
+    pub commands_tx: Arc<Mutex<Option<tokio::sync::mpsc::UnboundedSender<KickCommand>>>>,
 }
 
-pub async fn start_kick_oauth(app: AppHandle) -> Result<(), String> {
+pub async fn start_kick_oauth(app: AppHandle) -> Result<(), HeychatError> {
     let client_id = "01KG9BKAZPA62J13S6PATK3BWN";
     let redirect_uri = "https%3A%2F%2Fheychatapp.com%2Fauth"; // Use standard URL encoding
     // Use %20 for spaces
@@ -44,17 +59,18 @@ pub async fn start_kick_oauth(app: AppHandle) -> Result<(), String> {
         client_id, redirect_uri, scope, challenge
     );
 
-    app.opener().open_url(url, None::<&str>).map_err(|e: tauri_plugin_opener::Error| e.to_string())?;
+    app.opener().open_url(url, None::<&str>).map_err(|e: tauri_plugin_opener::Error| HeychatError::Other(e.to_string()))?;
     Ok(())
 }
 
-pub async fn exchange_kick_code(app: AppHandle, code: String) -> Result<(), String> {
+pub async fn exchange_kick_code(app: AppHandle, code: String) -> Result<(), HeychatError> {
     // Worker Proxy URL
     let token_endpoint = "https://ktp.heychatapp.com/";
     let redirect_uri = "https://heychatapp.com/auth";
     
     let state = app.state::<KickState>();
-    let verifier = state.pkce_verifier.lock().unwrap().take().ok_or("No PKCE verifier found")?;
+    let verifier = state.pkce_verifier.lock().unwrap().take()
+        .ok_or_else(|| HeychatError::Unauthorized("No PKCE verifier found; start the Kick login again".to_string()))?;
 
     let client = reqwest::Client::new();
     
@@ -71,16 +87,13 @@ pub async fn exchange_kick_code(app: AppHandle, code: String) -> Result<(), Stri
         .json(&payload)
         .header(ACCEPT, "application/json")
         .send()
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
 
     if !res.status().is_success() {
-        let status = res.status();
-        let body = res.text().await.unwrap_or_default();
-        return Err(format!("Token exchange failed: {} - {}", status, body));
+        return Err(HeychatError::from_response(res).await);
     }
 
-    let json: Value = res.json().await.map_err(|e| e.to_string())?;
+    let json: Value = res.json().await?;
     
     // The worker returns the exact response from Kick
     if let Some(token) = json["access_token"].as_str() {
@@ -89,7 +102,7 @@ pub async fn exchange_kick_code(app: AppHandle, code: String) -> Result<(), Stri
         Ok(())
     } else {
         eprintln!("Invalid response from Proxy: {:?}", json);
-        Err("No access_token used in response".to_string())
+        Err(HeychatError::Unauthorized("No access_token in token exchange response".to_string()))
     }
 }
 
@@ -100,14 +113,157 @@ pub async fn start_kick_handler(app: AppHandle, channel: String, chatroom_id: u6
     // Store broadcaster ID in state for sending later
     let state = app.state::<KickState>();
     state.broadcaster_ids.lock().unwrap().insert(channel_slug.clone(), broadcaster_user_id);
+    state.chatrooms.lock().unwrap().insert(chatroom_id, channel_slug.clone());
 
     let _ = app.emit("kick-connected", channel_slug.clone());
 
-    // Create shutdown channel
-    let (tx, mut rx) = tokio::sync::broadcast::channel(1);
+    // Reuse the shared socket if it is still running. The lock is held until the new sender is
+    // installed, so concurrent joins can't both start a socket.
+    let mut commands_tx = state.commands_tx.lock().unwrap();
+    if let Some(tx) = commands_tx.as_ref() {
+        if tx.send(KickCommand::Subscribe(chatroom_id)).is_ok() {
+            return;
+        }
+    }
+
+    // Create shutdown and command channels for a fresh socket
+    let (tx, rx) = tokio::sync::broadcast::channel(1);
+    let (new_commands_tx, commands_rx) = tokio::sync::mpsc::unbounded_channel();
     *state.shutdown_tx.lock().unwrap() = Some(tx);
+    *commands_tx = Some(new_commands_tx);
+    drop(commands_tx);
+
+    tauri::async_runtime::spawn(run_kick_socket(app.clone(), rx, commands_rx));
+}
+
+fn pusher_channel_name(chatroom_id: u64) -> String {
+    format!("chatrooms.{}.v2", chatroom_id)
+}
+
+// "chatrooms.{id}.v2" -> id
+fn parse_pusher_channel(name: &str) -> Option<u64> {
+    name.strip_prefix("chatrooms.")?.strip_suffix(".v2")?.parse().ok()
+}
+
+fn pusher_subscribe(chatroom_id: u64) -> Message {
+    let msg = json!({
+        "event": "pusher:subscribe",
+        "data": {
+            "auth": "",
+            "channel": pusher_channel_name(chatroom_id)
+        }
+    });
+    Message::Text(msg.to_string().into())
+}
+
+fn pusher_unsubscribe(chatroom_id: u64) -> Message {
+    let msg = json!({
+        "event": "pusher:unsubscribe",
+        "data": {
+            "channel": pusher_channel_name(chatroom_id)
+        }
+    });
+    Message::Text(msg.to_string().into())
+}
+
+// Reconnect backoff: 1s doubling up to a minute, plus up to 50% random jitter
+const KICK_BACKOFF_BASE_MS: u64 = 1_000;
+const KICK_BACKOFF_MAX_MS: u64 = 60_000;
+const KICK_MAX_RECONNECT_ATTEMPTS: u32 = 10;
+// A session has to stay up this long before it counts as healthy and resets the backoff;
+// a server that accepts and immediately closes keeps backing off
+const KICK_HEALTHY_SESSION: Duration = Duration::from_secs(30);
+
+// pusher-js defaults: ping after 120s of silence, give the server 30s to answer
+const PUSHER_DEFAULT_ACTIVITY_TIMEOUT: Duration = Duration::from_secs(120);
+const PUSHER_PONG_TIMEOUT: Duration = Duration::from_secs(30);
+
+// How a single Pusher session ended, and therefore what the supervisor does next
+#[derive(Debug, PartialEq)]
+enum SessionEnd {
+    Shutdown,
+    Fatal(String),
+    ReconnectNow,
+    Dropped,
+}
+
+// Pusher error code ranges (https://pusher.com/docs/channels/library_auth_reference/pusher-websockets-protocol/#error-codes)
+fn classify_pusher_code(code: u64, message: &str) -> Option<SessionEnd> {
+    match code {
+        4000..=4099 => Some(SessionEnd::Fatal(format!("Kick chat server refused the connection ({}): {}", code, message))),
+        4100..=4199 => Some(SessionEnd::Dropped),
+        4200..=4299 => Some(SessionEnd::ReconnectNow),
+        _ => None,
+    }
+}
+
+fn backoff_delay(attempt: u32) -> Duration {
+    let exp = KICK_BACKOFF_BASE_MS.saturating_mul(1 << attempt.min(16)).min(KICK_BACKOFF_MAX_MS);
+    let jitter = thread_rng().gen_range(0..=exp / 2);
+    Duration::from_millis(exp + jitter)
+}
+
+async fn run_kick_socket(
+    app: AppHandle,
+    mut rx: tokio::sync::broadcast::Receiver<()>,
+    mut commands_rx: tokio::sync::mpsc::UnboundedReceiver<KickCommand>,
+) {
+    let mut attempt: u32 = 0;
+
+    loop {
+        let reconnecting = attempt > 0;
+        let started = Instant::now();
+        let end = run_kick_session(&app, &mut rx, &mut commands_rx, reconnecting).await;
+        // Only a session that stayed up for a while resets the backoff
+        attempt = if started.elapsed() >= KICK_HEALTHY_SESSION { 1 } else { attempt + 1 };
+
+        let delay = match end {
+            SessionEnd::Shutdown => return,
+            SessionEnd::Fatal(reason) => {
+                eprintln!("Kick: {}", reason);
+                let _ = app.emit("kick-error", reason);
+                break;
+            }
+            _ if attempt > KICK_MAX_RECONNECT_ATTEMPTS => {
+                let _ = app.emit("kick-error", "Lost connection to Kick chat server.");
+                break;
+            }
+            // The server asked for an immediate reconnect; honour it unless it keeps asking
+            SessionEnd::ReconnectNow if attempt == 1 => Duration::ZERO,
+            SessionEnd::ReconnectNow | SessionEnd::Dropped => backoff_delay(attempt - 1),
+        };
+
+        eprintln!("Kick reconnecting in {:?} (attempt {})", delay, attempt);
+        let _ = app.emit("kick-reconnecting", json!({ "attempt": attempt, "delay_ms": delay.as_millis() as u64 }));
+
+        // Cancellable Sleep
+        tokio::select! {
+             biased;
+             _ = rx.recv() => return,
+             _ = tokio::time::sleep(delay) => {}
+        }
+    }
+
+    // Gave up: forget the chatrooms and this socket's channels, so a later join starts a fresh
+    // socket instead of sending commands nobody reads
+    drop(commands_rx);
+    drop(rx);
+    let state = app.state::<KickState>();
+    state.chatrooms.lock().unwrap().clear();
+    let mut commands_tx = state.commands_tx.lock().unwrap();
+    if commands_tx.as_ref().is_some_and(|tx| tx.is_closed()) {
+        *commands_tx = None;
+        *state.shutdown_tx.lock().unwrap() = None;
+    }
+}
 
-    // 2. Connect to Pusher (Read-Only)
+async fn run_kick_session(
+    app: &AppHandle,
+    rx: &mut tokio::sync::broadcast::Receiver<()>,
+    commands_rx: &mut tokio::sync::mpsc::UnboundedReceiver<KickCommand>,
+    reconnecting: bool,
+) -> SessionEnd {
+    // 1. Connect to Pusher (Read-Only)
     let ws_url = format!(
         "wss://ws-{}.pusher.com/app/{}?protocol=7&client=js&version=8.4.0-rc2&flash=false",
         PUSHER_CLUSTER, PUSHER_KEY
@@ -117,54 +273,119 @@ pub async fn start_kick_handler(app: AppHandle, channel: String, chatroom_id: u6
         Ok(s) => s,
         Err(e) => {
             eprintln!("Failed to connect to Kick WebSocket: {}", e);
-            let _ = app.emit("kick-error", "Failed to connect to Kick chat server.");
-            return;
+            return SessionEnd::Dropped;
         }
     };
 
     let (mut write, mut read) = ws_stream.split();
 
-    // 3. Subscribe
-    let subscribe_msg = serde_json::json!({
-        "event": "pusher:subscribe",
-        "data": {
-            "auth": "",
-            "channel": format!("chatrooms.{}.v2", chatroom_id)
+    // 2. Subscribe to every active chatroom; later joins arrive as commands
+    let active: Vec<(u64, String)> = app.state::<KickState>().chatrooms.lock().unwrap()
+        .iter()
+        .map(|(id, slug)| (*id, slug.clone()))
+        .collect();
+    let mut subscribed = HashSet::new();
+    for (chatroom_id, _) in &active {
+        if let Err(e) = write.send(pusher_subscribe(*chatroom_id)).await {
+             eprintln!("Kick subscribe failed: {}", e);
+             return SessionEnd::Dropped;
         }
-    });
+        subscribed.insert(*chatroom_id);
+    }
 
-    if let Err(e) = write.send(Message::Text(subscribe_msg.to_string().into())).await {
-         eprintln!("Kick subscribe failed: {}", e);
-         return;
+    if reconnecting {
+        eprintln!("Kick reconnected, re-subscribed to {} chatroom(s)", active.len());
+        let channels: Vec<String> = active.into_iter().map(|(_, slug)| slug).collect();
+        let _ = app.emit("kick-reconnected", channels);
     }
 
-    // 4. Loop with Shutdown
+    // 3. Loop with Shutdown, keeping the session alive with Pusher-level pings
+    let mut session = PusherSession::default();
+    let mut last_activity = Instant::now();
+    let mut ping_sent_at: Option<Instant> = None;
+
     loop {
+        let deadline = match ping_sent_at {
+            Some(sent) => sent + PUSHER_PONG_TIMEOUT,
+            None => last_activity + session.activity_timeout,
+        };
+
         tokio::select! {
              biased;
              _ = rx.recv() => {
                  eprintln!("Kick handler received shutdown signal.");
-                 break;
+                 let _ = write.send(Message::Close(None)).await;
+                 return SessionEnd::Shutdown;
+             }
+             Some(command) = commands_rx.recv() => {
+                 let result = match command {
+                     KickCommand::Subscribe(id) if subscribed.insert(id) => write.send(pusher_subscribe(id)).await,
+                     KickCommand::Unsubscribe(id) if subscribed.remove(&id) => write.send(pusher_unsubscribe(id)).await,
+                     _ => Ok(()),
+                 };
+                 if let Err(e) = result {
+                     eprintln!("Kick subscription update failed: {}", e);
+                     return SessionEnd::Dropped;
+                 }
+             }
+             _ = tokio::time::sleep_until(deadline) => {
+                 if ping_sent_at.is_some() {
+                     eprintln!("Kick: no pusher:pong within {:?}, reconnecting", PUSHER_PONG_TIMEOUT);
+                     return SessionEnd::Dropped;
+                 }
+                 if let Err(e) = write.send(pusher_event("pusher:ping")).await {
+                     eprintln!("Kick ping failed: {}", e);
+                     return SessionEnd::Dropped;
+                 }
+                 ping_sent_at = Some(Instant::now());
              }
              msg_result = read.next() => {
+                 // Any traffic from the server counts as activity, pong or not
+                 last_activity = Instant::now();
+                 ping_sent_at = None;
+
                  match msg_result {
                     Some(Ok(msg)) => {
                         match msg {
                             Message::Text(text) => {
                                 // eprintln!("Kick Raw WS: {}", text.chars().take(200).collect::<String>());
-                                handle_kick_message(&app, &text)
+                                let json: Value = serde_json::from_str(&text).unwrap_or(Value::Null);
+                                match json.get("event").and_then(|e| e.as_str()).unwrap_or("") {
+                                    "pusher:connection_established" => {
+                                        session = PusherSession::from_established(&json);
+                                        eprintln!("Kick Pusher session {:?} established (activity timeout {:?})", session.socket_id, session.activity_timeout);
+                                    }
+                                    "pusher:ping" => {
+                                        if let Err(e) = write.send(pusher_event("pusher:pong")).await {
+                                            eprintln!("Kick pong failed: {}", e);
+                                            return SessionEnd::Dropped;
+                                        }
+                                    }
+                                    "pusher:pong" => {}
+                                    "pusher:error" => {
+                                        if let Some(end) = pusher_error(&json) {
+                                            return end;
+                                        }
+                                    }
+                                    _ => handle_kick_message(app, &json),
+                                }
                             },
                             Message::Ping(ping) => { let _ = write.send(Message::Pong(ping)).await; },
+                            Message::Close(frame) => {
+                                eprintln!("Kick WS closed by server: {:?}", frame);
+                                let end = frame.and_then(|f| classify_pusher_code(u16::from(f.code) as u64, &f.reason));
+                                return end.unwrap_or(SessionEnd::Dropped);
+                            }
                             _ => {}
                         }
                     }
                     Some(Err(e)) => {
                         eprintln!("Kick WS Error: {}", e);
-                        break;
+                        return SessionEnd::Dropped;
                     }
                     None => {
                         eprintln!("Kick WS Stream ended");
-                        break;
+                        return SessionEnd::Dropped;
                     }
                  }
              }
@@ -172,23 +393,101 @@ pub async fn start_kick_handler(app: AppHandle, channel: String, chatroom_id: u6
     }
 }
 
+// Per-connection details announced by pusher:connection_established
+struct PusherSession {
+    socket_id: Option<String>,
+    activity_timeout: Duration,
+}
+
+impl Default for PusherSession {
+    fn default() -> Self {
+        PusherSession {
+            socket_id: None,
+            activity_timeout: PUSHER_DEFAULT_ACTIVITY_TIMEOUT,
+        }
+    }
+}
+
+impl PusherSession {
+    fn from_established(json: &Value) -> Self {
+        let data = pusher_data(json);
+        let socket_id = data.as_ref()
+            .and_then(|d| d.get("socket_id"))
+            .and_then(|s| s.as_str())
+            .map(|s| s.to_string());
+        // The server states how long it tolerates silence; never wait longer than the client default
+        let activity_timeout = data.as_ref()
+            .and_then(|d| d.get("activity_timeout"))
+            .and_then(|t| t.as_u64())
+            .map(Duration::from_secs)
+            .map_or(PUSHER_DEFAULT_ACTIVITY_TIMEOUT, |t| t.min(PUSHER_DEFAULT_ACTIVITY_TIMEOUT));
+        PusherSession { socket_id, activity_timeout }
+    }
+}
+
+// Pusher nests event payloads as JSON-encoded strings; protocol events may send a plain object instead
+fn pusher_data(json: &Value) -> Option<Value> {
+    match json.get("data")? {
+        Value::String(s) => serde_json::from_str(s).ok(),
+        v => Some(v.clone()),
+    }
+}
+
+fn pusher_event(event: &str) -> Message {
+    Message::Text(json!({ "event": event, "data": {} }).to_string().into())
+}
+
+// pusher:error carries a code telling the client whether (and how soon) to reconnect
+fn pusher_error(json: &Value) -> Option<SessionEnd> {
+    let data = pusher_data(json)?;
+    let code = data.get("code").and_then(|c| c.as_u64());
+    let message = data.get("message").and_then(|m| m.as_str()).unwrap_or("");
+    eprintln!("Kick pusher:error {:?}: {}", code, message);
+    classify_pusher_code(code?, message)
+}
+
 pub async fn leave_kick_channel(app: AppHandle, channel: String) {
-    eprintln!("Leaving Kick channel: {}", channel);
+    let channel_slug = channel.trim().to_lowercase();
+    eprintln!("Leaving Kick channel: {}", channel_slug);
     let state = app.state::<KickState>();
-    // Clone Arc to avoid lifetime issues with State borrow
-    let shutdown_arc = state.shutdown_tx.clone();
-    
-    let tx_opt = {
-        let mut guard = shutdown_arc.lock().unwrap();
-        guard.take()
+
+    let (removed, now_empty) = {
+        let mut chatrooms = state.chatrooms.lock().unwrap();
+        let removed: Vec<u64> = chatrooms.iter()
+            .filter(|(_, slug)| **slug == channel_slug)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in &removed {
+            chatrooms.remove(id);
+        }
+        (removed, chatrooms.is_empty())
     };
 
-    if let Some(tx) = tx_opt {
-        let _ = tx.send(());
+    let commands_tx = state.commands_tx.lock().unwrap().clone();
+    if let Some(tx) = commands_tx {
+        for chatroom_id in removed {
+            let _ = tx.send(KickCommand::Unsubscribe(chatroom_id));
+        }
+    }
+
+    // Last chatroom gone: close the shared socket
+    if now_empty {
+        // Clone Arc to avoid lifetime issues with State borrow
+        let shutdown_arc = state.shutdown_tx.clone();
+        
+        let tx_opt = {
+            let mut guard = shutdown_arc.lock().unwrap();
+            guard.take()
+        };
+
+        if let Some(tx) = tx_opt {
+            let _ = tx.send(());
+        }
+        *state.commands_tx.lock().unwrap() = None;
     }
 }
 
-pub async fn send_kick_message(app: AppHandle, channel: String, message: String, token: String) -> Result<(), String> {
+pub async fn send_kick_message(app: AppHandle, channel: String, message: String, token: String) -> Result<(), HeychatError> {
     let channel_slug = channel.trim().to_lowercase();
     
     // Retrieve broadcaster_user_id from state
@@ -207,7 +506,7 @@ pub async fn send_kick_message(app: AppHandle, channel: String, message: String,
                     state.broadcaster_ids.lock().unwrap().insert(channel_slug.clone(), uid);
                     uid
                 },
-                Err(_) => return Err("Could not resolve channel ID for sending".to_string())
+                Err(e) => return Err(HeychatError::NotFound(format!("Could not resolve Kick channel {}: {}", channel_slug, e)))
             }
         }
     };
@@ -228,13 +527,10 @@ pub async fn send_kick_message(app: AppHandle, channel: String, message: String,
         .header(ACCEPT, "application/json")
         .json(&payload)
         .send()
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
 
     if !res.status().is_success() {
-        let status = res.status();
-        let body = res.text().await.unwrap_or_default();
-        return Err(format!("Send failed ({}): {}", status, body));
+        return Err(HeychatError::from_response(res).await);
     }
 
     Ok(())
@@ -270,32 +566,296 @@ async fn get_channel_info_v2(slug: &str) -> Result<(u64, u64), String> {
     Ok((chatroom_id, user_id))
 }
 
-fn handle_kick_message(app: &AppHandle, text: &str) {
-    let json: Value = match serde_json::from_str(text) {
-        Ok(v) => v,
-        Err(_) => return,
-    };
-
+fn handle_kick_message(app: &AppHandle, json: &Value) {
     let event = match json.get("event").and_then(|e| e.as_str()) {
         Some(e) => e,
         None => return,
     };
 
-    if event == "App\\Events\\ChatMessageEvent" {
-        if let Some(data_str) = json.get("data").and_then(|d| d.as_str()) {
-             if let Ok(data_json) = serde_json::from_str::<Value>(data_str) {
-                 process_chat_message(app, &data_json);
-             }
+    // Resolve "chatrooms.{id}.v2" back to the channel slug it was joined as
+    let source = match json.get("channel").and_then(|c| c.as_str()).and_then(parse_pusher_channel) {
+        Some(chatroom_id) => kick_source(app, chatroom_id),
+        None => return,
+    };
+
+    let data = match pusher_data(json) {
+        Some(d) => d,
+        None => return,
+    };
+
+    // Kick's payload shapes are undocumented; anything that fails to deserialize is logged and dropped
+    let result = match event.trim_start_matches("App\\Events\\") {
+        "ChatMessageEvent" => {
+            let _ = app.emit("chat-message", parse_chat_message(source, &data));
+            Ok(())
+        }
+        "MessageDeletedEvent" => serde_json::from_value::<KickMessageDeleted>(data).map(|d| {
+            emit_moderation(app, source, ModerationAction::MessageDeleted { message_id: d.message.id });
+        }),
+        "UserBannedEvent" => serde_json::from_value::<KickUserBanned>(data).map(|d| {
+            let username = d.user.username;
+            let user_id = d.user.id.map(|id| id.to_string());
+            let action = match d.duration {
+                // Kick reports temporary bans in minutes
+                Some(minutes) if !d.permanent => ModerationAction::UserTimedOut { username, user_id, duration_secs: minutes * 60 },
+                _ => ModerationAction::UserBanned { username, user_id },
+            };
+            emit_moderation(app, source, action);
+        }),
+        "UserUnbannedEvent" => serde_json::from_value::<KickUserUnbanned>(data).map(|d| {
+            emit_moderation(app, source, ModerationAction::UserUnbanned {
+                username: d.user.username,
+                user_id: d.user.id.map(|id| id.to_string()),
+            });
+        }),
+        "ChatroomClearEvent" => {
+            emit_moderation(app, source, ModerationAction::ChatCleared);
+            Ok(())
         }
+        "SubscriptionEvent" => serde_json::from_value::<KickSubscription>(data).map(|d| {
+            let (msg_type, system_message) = match d.months {
+                Some(months) if months > 1 => (
+                    MessageType::Resub { tier: None, months: months as u64, streak_months: None },
+                    format!("{} subscribed for {} months!", d.username, months),
+                ),
+                _ => (MessageType::Sub { tier: None }, format!("{} subscribed!", d.username)),
+            };
+            let _ = app.emit("chat-message", kick_notice(source, msg_type, d.username, system_message, String::new()));
+        }),
+        "GiftedSubscriptionsEvent" => serde_json::from_value::<KickGiftedSubscriptions>(data).map(|d| {
+            let count = d.gifted_usernames.len();
+            let system_message = match d.gifter_total {
+                Some(total) if total > count as u64 => format!("Gifted {} subs! ({} gifted in total)", count, total),
+                _ => format!("Gifted {} subs!", count),
+            };
+            let msg_type = MessageType::SubGift {
+                tier: None,
+                count: count as u64,
+                recipient: None,
+                gifted_months: None,
+                gifter_total: d.gifter_total,
+                anonymous: false,
+            };
+            let _ = app.emit("chat-message", kick_notice(source, msg_type, d.gifter_username, system_message, d.gifted_usernames.join(", ")));
+        }),
+        "StreamHostEvent" => serde_json::from_value::<KickStreamHost>(data).map(|d| {
+            let system_message = match d.number_viewers {
+                Some(viewers) => format!("{} is hosting with {} viewers!", d.host_username, viewers),
+                None => format!("{} is hosting!", d.host_username),
+            };
+            let msg_type = MessageType::Raid { viewer_count: d.number_viewers.unwrap_or(0) };
+            let _ = app.emit("chat-message", kick_notice(source, msg_type, d.host_username, system_message, d.optional_message.unwrap_or_default()));
+        }),
+        "PinnedMessageCreatedEvent" => serde_json::from_value::<KickPinnedMessage>(data).map(|d| {
+            let message = parse_chat_message(source.clone(), &d.message);
+            let _ = app.emit("kick-pinned-message", json!({ "source": source, "message": message, "duration": d.duration }));
+        }),
+        "PinnedMessageDeletedEvent" => {
+            let _ = app.emit("kick-pinned-message", json!({ "source": source, "message": Value::Null }));
+            Ok(())
+        }
+        "PollUpdateEvent" => serde_json::from_value::<KickPollUpdate>(data).map(|d| {
+            let _ = app.emit("kick-poll-update", json!({ "source": source, "poll": d.poll }));
+        }),
+        "ChatroomUpdatedEvent" => serde_json::from_value::<KickChatroomSettings>(data).map(|d| {
+            let _ = app.emit("kick-chatroom-updated", json!({ "source": source, "settings": d }));
+        }),
+        _ => Ok(()),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Failed to parse Kick {}: {}", event, e);
     }
 }
 
-fn process_chat_message(app: &AppHandle, data: &Value) {
+#[derive(Deserialize, Debug)]
+struct KickUserRef {
+    id: Option<u64>,
+    username: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct KickMessageRef {
+    id: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct KickMessageDeleted {
+    message: KickMessageRef,
+}
+
+#[derive(Deserialize, Debug)]
+struct KickUserBanned {
+    user: KickUserRef,
+    #[serde(default)]
+    permanent: bool,
+    duration: Option<u64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct KickUserUnbanned {
+    user: KickUserRef,
+}
+
+#[derive(Deserialize, Debug)]
+struct KickSubscription {
+    username: String,
+    months: Option<u32>,
+}
+
+#[derive(Deserialize, Debug)]
+struct KickGiftedSubscriptions {
+    gifter_username: String,
+    #[serde(default)]
+    gifted_usernames: Vec<String>,
+    gifter_total: Option<u64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct KickStreamHost {
+    host_username: String,
+    number_viewers: Option<u64>,
+    optional_message: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct KickPinnedMessage {
+    message: Value,
+    duration: Option<u64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct KickPollUpdate {
+    poll: KickPoll,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KickPoll {
+    pub title: String,
+    #[serde(default)]
+    pub options: Vec<KickPollOption>,
+    pub duration: Option<u64>,
+    pub remaining: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KickPollOption {
+    pub id: u64,
+    pub label: String,
+    #[serde(default)]
+    pub votes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KickChatroomSettings {
+    pub slow_mode: Option<KickChatMode>,
+    pub subscribers_mode: Option<KickChatMode>,
+    pub followers_mode: Option<KickChatMode>,
+    pub emotes_mode: Option<KickChatMode>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KickChatMode {
+    #[serde(default)]
+    pub enabled: bool,
+    pub message_interval: Option<u64>, // slow mode, seconds
+    pub min_duration: Option<u64>, // followers mode, minutes
+}
+
+fn emit_moderation(app: &AppHandle, source: ChatSource, action: ModerationAction) {
+    let _ = app.emit("chat-moderation", ModerationEvent::now(source, action));
+}
+
+// Subs, gifts and hosts are rendered like Twitch UserNotices: a system line plus optional user text
+// Notices have no id from Kick; the frontend keys messages by id, so each one needs its own
+static KICK_NOTICE_SEQ: AtomicU64 = AtomicU64::new(0);
+
+fn kick_notice(source: ChatSource, msg_type: MessageType, username: String, system_message: String, message: String) -> ChatMessage {
+    ChatMessage {
+        id: format!("kick-notice-{}-{}", chrono::Utc::now().timestamp_millis(), KICK_NOTICE_SEQ.fetch_add(1, Ordering::Relaxed)),
+        platform: Platform::Kick,
+        source,
+        username,
+        login: None,
+        message,
+        color: Some("#53FC18".to_string()),
+        badges: vec![],
+        badge_images: HashMap::new(),
+        is_mod: false,
+        is_vip: false,
+        is_member: false,
+        timestamp: chrono::Utc::now(),
+        received_at: chrono::Utc::now(),
+        emotes: vec![],
+        msg_type,
+        system_message: Some(system_message),
+        video_offset_ms: None,
+        user_id: None,
+        avatar_url: None,
+    }
+}
+
+// Kick sends created_at as RFC 3339, e.g. "2024-05-01T12:34:56+00:00"
+fn kick_timestamp(data: &Value) -> Option<chrono::DateTime<chrono::Utc>> {
+    let created_at = data.get("created_at")?.as_str()?;
+    chrono::DateTime::parse_from_rfc3339(created_at).ok().map(|t| t.with_timezone(&chrono::Utc))
+}
+
+fn kick_source(app: &AppHandle, chatroom_id: u64) -> ChatSource {
+    let state = app.state::<KickState>();
+    let slug = state.chatrooms.lock().unwrap().get(&chatroom_id).cloned();
+    ChatSource {
+        platform: Platform::Kick,
+        // A message can still be in flight right after leaving; fall back to the raw id
+        channel: slug.clone().unwrap_or_else(|| chatroom_id.to_string()),
+        room_id: Some(chatroom_id.to_string()),
+        display_name: slug,
+    }
+}
+
+// Kick inlines emotes as "[emote:ID:name]". Each token is replaced with its name, recording
+// the name's char range (exclusive end) pointing at the CDN image.
+static KICK_EMOTE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[emote:(\d+):([^\]]+)\]").unwrap());
+
+fn parse_kick_emotes(content: &str) -> (String, Vec<Emote>) {
+    let mut message = String::with_capacity(content.len());
+    let mut emotes = Vec::new();
+    let mut char_len = 0;
+    let mut last = 0;
+
+    for caps in KICK_EMOTE_REGEX.captures_iter(content) {
+        let token = caps.get(0).unwrap();
+        let before = &content[last..token.start()];
+        message.push_str(before);
+        char_len += before.chars().count();
+
+        let name = &caps[2];
+        let name_len = name.chars().count();
+        emotes.push(Emote {
+            id: format!("https://files.kick.com/emotes/{}/fullsize", &caps[1]),
+            code: name.to_string(),
+            start: char_len,
+            end: char_len + name_len,
+        });
+        message.push_str(name);
+        char_len += name_len;
+        last = token.end();
+    }
+    message.push_str(&content[last..]);
+
+    (message, emotes)
+}
+
+fn parse_chat_message(source: ChatSource, data: &Value) -> ChatMessage {
+    let received_at = chrono::Utc::now();
     let id = data.get("id").and_then(|i| i.as_str()).unwrap_or("").to_string();
-    let message = data.get("content").and_then(|c| c.as_str()).unwrap_or("").to_string();
+    let content = data.get("content").and_then(|c| c.as_str()).unwrap_or("");
+    let (message, emotes) = parse_kick_emotes(content);
     
     let sender = data.get("sender");
     let username = sender.and_then(|s| s.get("username")).and_then(|u| u.as_str()).unwrap_or("Unknown").to_string();
+    let login = sender.and_then(|s| s.get("slug")).and_then(|u| u.as_str()).map(|s| s.to_string());
+    let user_id = sender.and_then(|s| s.get("id")).and_then(|i| i.as_u64()).map(|id| id.to_string());
     
     let identity = sender.and_then(|s| s.get("identity"));
     let color = identity.and_then(|i| i.get("color")).and_then(|c| c.as_str()).map(|c| c.to_string());
@@ -314,21 +874,100 @@ fn process_chat_message(app: &AppHandle, data: &Value) {
         }
     }
 
-    let chat_message = ChatMessage {
+    ChatMessage {
         id,
         platform: Platform::Kick,
+        source,
         username,
+        login,
         message,
         color,
         badges,
+        badge_images: HashMap::new(),
         is_mod,
         is_vip,
         is_member: false, 
-        timestamp: chrono::Local::now().to_rfc3339(),
-        emotes: vec![], // Emotes TODO
-        msg_type: "chat".to_string(),
+        timestamp: kick_timestamp(data).unwrap_or(received_at),
+        received_at,
+        emotes,
+        msg_type: MessageType::Chat,
         system_message: None,
-    };
+        video_offset_ms: None,
+        user_id,
+        avatar_url: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_pusher_close_codes() {
+        assert!(matches!(classify_pusher_code(4001, "Application disabled"), Some(SessionEnd::Fatal(reason)) if reason.contains("4001")));
+        assert_eq!(classify_pusher_code(4100, "Over capacity"), Some(SessionEnd::Dropped));
+        assert_eq!(classify_pusher_code(4199, ""), Some(SessionEnd::Dropped));
+        assert_eq!(classify_pusher_code(4200, "Generic reconnect"), Some(SessionEnd::ReconnectNow));
+        assert_eq!(classify_pusher_code(4301, "Client event rejected"), None);
+        assert_eq!(classify_pusher_code(1000, ""), None);
+    }
 
-    let _ = app.emit("chat-message", chat_message);
+    fn emote_ranges(message: &str, emotes: &[Emote]) -> Vec<String> {
+        let chars: Vec<char> = message.chars().collect();
+        emotes.iter().map(|e| chars[e.start..e.end].iter().collect()).collect()
+    }
+
+    #[test]
+    fn replaces_multiple_emotes_with_their_names() {
+        let (message, emotes) = parse_kick_emotes("hi [emote:37226:KEKW] and [emote:39261:kkHuh] ok");
+        assert_eq!(message, "hi KEKW and kkHuh ok");
+        assert_eq!(emotes.len(), 2);
+        assert_eq!(emotes[0].id, "https://files.kick.com/emotes/37226/fullsize");
+        assert_eq!((emotes[0].start, emotes[0].end), (3, 7));
+        assert_eq!(emote_ranges(&message, &emotes), ["KEKW", "kkHuh"]);
+    }
+
+    #[test]
+    fn adjacent_emotes_do_not_overlap() {
+        let (message, emotes) = parse_kick_emotes("[emote:1:A][emote:2:BB]");
+        assert_eq!(message, "ABB");
+        assert_eq!((emotes[0].start, emotes[0].end), (0, 1));
+        assert_eq!((emotes[1].start, emotes[1].end), (1, 3));
+    }
+
+    #[test]
+    fn emote_ranges_count_chars_not_bytes() {
+        let (message, emotes) = parse_kick_emotes("héllo 🎉 [emote:5:Pog] ü");
+        assert_eq!(message, "héllo 🎉 Pog ü");
+        assert_eq!((emotes[0].start, emotes[0].end), (8, 11));
+        assert_eq!(emote_ranges(&message, &emotes), ["Pog"]);
+    }
+
+    #[test]
+    fn text_without_emotes_is_unchanged() {
+        let (message, emotes) = parse_kick_emotes("[emote:abc:nope] plain");
+        assert_eq!(message, "[emote:abc:nope] plain");
+        assert!(emotes.is_empty());
+    }
+
+    #[test]
+    fn notices_get_distinct_ids() {
+        let source = ChatSource { platform: Platform::Kick, channel: "xqc".to_string(), display_name: None, room_id: None };
+        let a = kick_notice(source.clone(), MessageType::Chat, "a".to_string(), String::new(), String::new());
+        let b = kick_notice(source, MessageType::Chat, "b".to_string(), String::new(), String::new());
+        assert_ne!(a.id, b.id);
+    }
+
+    #[test]
+    fn backoff_doubles_with_jitter_up_to_the_cap() {
+        for attempt in 0..4 {
+            let base = KICK_BACKOFF_BASE_MS << attempt;
+            let delay = backoff_delay(attempt).as_millis() as u64;
+            assert!((base..=base + base / 2).contains(&delay), "attempt {}: {}ms", attempt, delay);
+        }
+        for attempt in [6, 10, 40] {
+            let delay = backoff_delay(attempt).as_millis() as u64;
+            assert!((KICK_BACKOFF_MAX_MS..=KICK_BACKOFF_MAX_MS * 3 / 2).contains(&delay), "attempt {}: {}ms", attempt, delay);
+        }
+    }
 }
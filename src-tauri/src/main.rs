@@ -1,8 +1,11 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod error;
+mod helix;
 mod models;
 mod twitch;
 mod youtube;
+mod youtube_bootstrap;
 mod kick;
 
 
@@ -10,6 +13,7 @@ use tauri::AppHandle;
 use twitch::start_twitch_handler;
 use youtube::{start_youtube_handler, send_youtube_message};
 use kick::exchange_kick_code;
+use error::HeychatError;
 
 use tauri::{Manager, Listener, Emitter};
 use tauri_plugin_opener::OpenerExt;
@@ -38,7 +42,7 @@ async fn leave_twitch(app: AppHandle, channel: String) {
 }
 
 #[tauri::command]
-async fn send_twitch_message(state: tauri::State<'_, twitch::TwitchAppState>, channel: String, message: String) -> Result<(), String> {
+async fn send_twitch_message(state: tauri::State<'_, twitch::TwitchAppState>, channel: String, message: String) -> Result<(), HeychatError> {
     let client = state.client.read().unwrap().as_ref().cloned();
 
     if let Some(client) = client {
@@ -46,25 +50,25 @@ async fn send_twitch_message(state: tauri::State<'_, twitch::TwitchAppState>, ch
         let channel = channel.trim().trim_start_matches('#').to_lowercase();
         
         eprintln!("Sending message to '{}': {}", channel, message);
-        client.privmsg(channel, message).await.map_err(|e| e.to_string())?;
+        client.privmsg(channel, message).await.map_err(|e| HeychatError::Network(e.to_string()))?;
         Ok(())
     } else {
-        Err("Twitch client not connected".to_string())
+        Err(HeychatError::NotConnected("Twitch client not connected".to_string()))
     }
 }
 
 
 
 #[tauri::command]
-fn join_youtube(app: AppHandle, video_id: String) {
+fn join_youtube(app: AppHandle, video_id: String, mode: Option<youtube_bootstrap::ChatMode>, replay: Option<youtube::ReplayOptions>) {
     tauri::async_runtime::spawn(async move {
-        start_youtube_handler(app, video_id).await;
+        start_youtube_handler(app, video_id, mode.unwrap_or_default(), replay.unwrap_or_default()).await;
     });
 }
 
 #[tauri::command]
-async fn leave_youtube(app: AppHandle) {
-    youtube::leave_youtube_channel(app).await;
+async fn leave_youtube(app: AppHandle, video_id: String) {
+    youtube::leave_youtube_channel(app, video_id).await;
 }
 
 #[tauri::command]
@@ -83,12 +87,12 @@ async fn leave_kick(app: AppHandle, channel: String) {
 }
 
 #[tauri::command]
-async fn start_kick_oauth(app: AppHandle) -> Result<(), String> {
+async fn start_kick_oauth(app: AppHandle) -> Result<(), HeychatError> {
     kick::start_kick_oauth(app).await
 }
 
 #[tauri::command]
-async fn send_kick_message(app: AppHandle, channel: String, message: String, token: String) -> Result<(), String> {
+async fn send_kick_message(app: AppHandle, channel: String, message: String, token: String) -> Result<(), HeychatError> {
     kick::send_kick_message(app, channel, message, token).await
 }
 
@@ -111,17 +115,24 @@ fn main() {
             app.manage(twitch::TwitchAppState {
                 client: std::sync::RwLock::new(None),
                 access_token: std::sync::RwLock::new(None),
+                token: std::sync::RwLock::new(None),
                 channel_id: std::sync::RwLock::new(None),
                 api_client: reqwest::Client::new(),
+                helix: helix::HelixClient::new(reqwest::Client::new(), helix::HELIX_BASE_URL),
                 shutdown_tx: std::sync::RwLock::new(None),
+                login: std::sync::RwLock::new(None),
+                channels: std::sync::RwLock::new(std::collections::HashMap::new()),
             });
             app.manage(kick::KickState {
                 broadcaster_ids: std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new())),
                 pkce_verifier: std::sync::Arc::new(std::sync::Mutex::new(None)),
                 shutdown_tx: std::sync::Arc::new(std::sync::Mutex::new(None)),
+                chatrooms: std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new())),
+                commands_tx: std::sync::Arc::new(std::sync::Mutex::new(None)),
             });
             app.manage(youtube::YouTubeState {
-                shutdown_tx: std::sync::RwLock::new(None),
+                pollers: std::sync::RwLock::new(std::collections::HashMap::new()),
+                send_sessions: std::sync::RwLock::new(std::collections::HashMap::new()),
             });
 
             let icon_content = include_bytes!("../icons/icon.png");
@@ -179,6 +190,7 @@ fn main() {
             start_twitch_oauth,
             start_youtube_oauth,
             send_youtube_message,
+            youtube::control_youtube_replay,
             twitch::twitch_ban_user,
             twitch::twitch_create_poll,
             twitch::twitch_create_prediction,
@@ -191,7 +203,7 @@ fn main() {
 }
 
 #[tauri::command]
-async fn start_twitch_oauth(app: AppHandle) -> Result<(), String> {
+async fn start_twitch_oauth(app: AppHandle) -> Result<(), HeychatError> {
     let client_id = "j07v9449bxjpfqx1msfnceaol2uwhx"; 
     let redirect_uri_encoded = "https%3A%2F%2Fheychatapp.com%2Fauth"; 
     
@@ -201,12 +213,12 @@ async fn start_twitch_oauth(app: AppHandle) -> Result<(), String> {
         client_id, redirect_uri_encoded
     );
 
-    app.opener().open_url(url, None::<&str>).map_err(|e| e.to_string())?;
+    app.opener().open_url(url, None::<&str>).map_err(|e| HeychatError::Other(e.to_string()))?;
     Ok(())
 }
 
 #[tauri::command]
-async fn start_youtube_oauth(app: AppHandle) -> Result<(), String> {
+async fn start_youtube_oauth(app: AppHandle) -> Result<(), HeychatError> {
     let client_id = "672007843378-gdj25iqn8h3eu6mp8qmqbfuvonuc2fkl.apps.googleusercontent.com";
     let redirect_uri_encoded = "https%3A%2F%2Fheychatapp.com%2Fauth";
     // Scopes: youtube.force-ssl + email + profile + openid
@@ -218,6 +230,6 @@ async fn start_youtube_oauth(app: AppHandle) -> Result<(), String> {
         client_id, redirect_uri_encoded, scope_encoded
     );
 
-    app.opener().open_url(url, None::<&str>).map_err(|e| e.to_string())?;
+    app.opener().open_url(url, None::<&str>).map_err(|e| HeychatError::Other(e.to_string()))?;
     Ok(())
 }
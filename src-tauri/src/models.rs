@@ -1,4 +1,6 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Platform {
@@ -8,6 +10,8 @@ pub enum Platform {
 
 }
 
+// A native emote inside `ChatMessage::message`. `start` and `end` count Unicode scalar values
+// (Rust chars, JS code points), with `end` exclusive, the same as Twitch's emote ranges.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Emote {
     pub id: String,
@@ -16,19 +20,108 @@ pub struct Emote {
     pub end: usize,
 }
 
+// Where a message was received: the Twitch channel, Kick chatroom or YouTube video
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatSource {
+    pub platform: Platform,
+    pub channel: String, // Twitch login, Kick slug or YouTube video id
+    pub room_id: Option<String>, // Twitch room-id or Kick chatroom id
+    pub display_name: Option<String>,
+}
+
+// What kind of line a ChatMessage is, along with the data that kind carries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MessageType {
+    Chat,
+    Action, // "/me" messages
+    Sub { tier: Option<String> },
+    Resub { tier: Option<String>, months: u64, streak_months: Option<u64> },
+    SubGift {
+        tier: Option<String>,
+        count: u64,
+        recipient: Option<String>, // Only for single gifts; community gifts announce each recipient separately
+        gifted_months: Option<u64>,
+        gifter_total: Option<u64>,
+        anonymous: bool,
+    },
+    GiftPaidUpgrade { gifter: Option<String> }, // None when the original gift was anonymous
+    Raid { viewer_count: u64 },
+    BitsBadgeTier { threshold: u64 },
+    Ritual { name: String },
+    Cheer { bits: u64 },
+    Redemption { reward_id: String },
+    Announcement { color: Option<String> },
+    // YouTube paid messages. `amount` is the numeric part of the display price ("5.00"),
+    // `currency` whatever symbol or code surrounds it ("$", "PHP"). Colours are "#RRGGBB" tier colours.
+    SuperChat {
+        amount: String,
+        currency: Option<String>,
+        header_color: Option<String>,
+        body_color: Option<String>,
+        text_color: Option<String>,
+    },
+    SuperSticker {
+        amount: String,
+        currency: Option<String>,
+        sticker_url: Option<String>,
+        sticker_label: Option<String>, // Alt text, e.g. "Cat waving"
+        color: Option<String>,
+    },
+    Membership { level: Option<String>, months: Option<u64> }, // months is None for new members
+    MembershipGift { count: u64 },
+    System,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
     pub id: String,
     pub platform: Platform,
-    pub username: String,
+    pub source: ChatSource,
+    pub username: String, // Display name
+    pub login: Option<String>, // Twitch login or Kick slug of the sender; None on YouTube and Kick notices
     pub message: String,
     pub color: Option<String>,
     pub badges: Vec<String>,
+    pub badge_images: HashMap<String, String>, // Badge name -> image URL, for badges the platform sends an image for
     pub is_mod: bool,
     pub is_vip: bool,
     pub is_member: bool,
-    pub timestamp: String,
+    pub timestamp: DateTime<Utc>, // When the platform says it was sent; the receive time if it doesn't say
+    pub received_at: DateTime<Utc>,
     pub emotes: Vec<Emote>,
-    pub msg_type: String, // "chat" or "sub"
+    pub msg_type: MessageType,
     pub system_message: Option<String>,
+    pub video_offset_ms: Option<u64>, // Position in the video, for replayed YouTube chat
+    pub user_id: Option<String>, // Twitch/Kick user id, YouTube author channel id
+    pub avatar_url: Option<String>,
+}
+
+// Moderation actions that hide or strike out previously emitted messages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ModerationAction {
+    MessageDeleted { message_id: String },
+    UserTimedOut { username: String, user_id: Option<String>, duration_secs: u64 },
+    UserBanned { username: String, user_id: Option<String> },
+    UserUnbanned { username: String, user_id: Option<String> },
+    ChatCleared,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModerationEvent {
+    pub source: ChatSource,
+    pub action: ModerationAction,
+    pub timestamp: DateTime<Utc>, // Same convention as ChatMessage::timestamp
+}
+
+impl ModerationEvent {
+    // For platforms that don't say when the action happened
+    pub fn now(source: ChatSource, action: ModerationAction) -> Self {
+        ModerationEvent {
+            source,
+            action,
+            timestamp: Utc::now(),
+        }
+    }
 }
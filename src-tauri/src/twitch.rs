@@ -1,16 +1,14 @@
-use crate::models::{ChatMessage, Platform};
+use crate::error::HeychatError;
+use crate::helix::{self, HelixAuth, HelixClient};
+use crate::models::{ChatMessage, ChatSource, MessageType, ModerationAction, ModerationEvent, Platform};
 use tauri::{AppHandle, Emitter, Manager};
 use twitch_irc::login::StaticLoginCredentials;
-use twitch_irc::message::ServerMessage;
+use twitch_irc::message::{ClearChatAction, ServerMessage, UserNoticeEvent, UserNoticeMessage};
 use twitch_irc::{ClientConfig, SecureTCPTransport, TwitchIRCClient};
 use std::sync::RwLock;
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
-struct HelixResponse<T> {
-    data: Vec<T>,
-}
-
 #[derive(Serialize, Deserialize, Debug)]
 struct IvrResponse {
     #[serde(rename = "followedAt")]
@@ -29,24 +27,59 @@ struct IvrMeta {
     tier: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct TwitchUser {
-    id: String,
-    login: String,
-    display_name: String,
-    profile_image_url: Option<String>,
-    created_at: Option<String>,
+
+type TwitchClient = TwitchIRCClient<SecureTCPTransport, StaticLoginCredentials>;
+
+// Per-channel bookkeeping for everything joined on the shared IRC client
+#[derive(Debug, Clone, Default)]
+pub struct TwitchChannel {
+    pub room_id: Option<String>,
+    pub is_mod: bool,
+    pub badges: Vec<String>,
 }
 
+// What id.twitch.tv/oauth2/validate reported for the current access token
+#[derive(Deserialize, Debug, Clone)]
+pub struct TwitchToken {
+    pub client_id: String,
+    pub user_id: Option<String>, // Absent for app access tokens
+    pub login: Option<String>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    pub expires_in: u64, // Seconds, as of validation
+    #[serde(skip, default = "chrono::Utc::now")]
+    pub validated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl TwitchToken {
+    // None for tokens that never expire, which validate reports as expires_in 0
+    pub fn expires_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        (self.expires_in > 0).then(|| self.validated_at + chrono::Duration::seconds(self.expires_in as i64))
+    }
+
+    pub fn is_expired(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        matches!(self.expires_at(), Some(at) if at <= now)
+    }
+}
+
+// Twitch requires apps to re-validate user tokens hourly
+const TWITCH_TOKEN_REVALIDATE: std::time::Duration = std::time::Duration::from_secs(60 * 60);
 
 pub struct TwitchAppState {
-    pub client: RwLock<Option<TwitchIRCClient<SecureTCPTransport, StaticLoginCredentials>>>,
+    pub client: RwLock<Option<TwitchClient>>,
     pub access_token: RwLock<Option<String>>,
+    pub token: RwLock<Option<TwitchToken>>, // Validation of access_token, filled in the background
     pub channel_id: RwLock<Option<String>>, // Broadcaster ID
     pub api_client: reqwest::Client,
+    pub helix: HelixClient,
     pub shutdown_tx: RwLock<Option<tokio::sync::broadcast::Sender<()>>>,
+    pub login: RwLock<Option<String>>, // Login the shared client authenticated as (None = anonymous)
+    pub channels: RwLock<HashMap<String, TwitchChannel>>, // Map<ChannelLogin, TwitchChannel>
 }
 
+fn clean_channel(channel: &str) -> String {
+    channel.trim().trim_start_matches('#').to_lowercase()
+}
 
 pub async fn start_twitch_handler(
     app: AppHandle,
@@ -54,13 +87,53 @@ pub async fn start_twitch_handler(
     username: Option<String>,
     token: Option<String>,
 ) {
-    let channel = if channel.starts_with('#') {
-        channel
-    } else {
-        format!("#{}", channel)
+    let state = match app.try_state::<TwitchAppState>() {
+        Some(s) => s,
+        None => return,
+    };
+
+    // Only an authenticated join carries an identity; a lone username still connects anonymously
+    let login = match (&username, &token) {
+        (Some(u), Some(_)) => Some(u.trim().to_lowercase()),
+        _ => None,
+    };
+
+    // Reuse the running client unless a different authenticated identity arrives, in which case
+    // it gets rebuilt. A join without credentials rides on whatever client is already running, so
+    // it never downgrades an authenticated connection to read-only.
+    let existing = {
+        let reusable = login.is_none() || *state.login.read().unwrap() == login;
+        state.client.read().unwrap().clone().filter(|_| reusable)
+    };
+
+    let client = match existing {
+        Some(client) => {
+            if let Some(t) = &token {
+                set_access_token(&app, t);
+            }
+            client
+        }
+        None => spawn_twitch_client(&app, login, token),
     };
 
-    let config = if let (Some(u), Some(t)) = (username, token.clone()) {
+    // Handle channel name: Stripping hash and ensuring lowercase/trimmed
+    let channel_clean = clean_channel(&channel);
+    state.channels.write().unwrap().entry(channel_clean.clone()).or_default();
+
+    eprintln!("Attempting to join Twitch channel: {}", channel_clean);
+
+    // TwitchIRCClient buffers commands until the connection is up, so joining right away is fine.
+    if let Err(e) = client.join(channel_clean) {
+         eprintln!("Failed to join twitch channel: {}", e);
+    } else {
+         eprintln!("Joined Twitch channel request sent.");
+    }
+}
+
+// Builds a new shared client, replacing (and shutting down) any previous one.
+// Channels already in the registry are re-joined on the new connection.
+fn spawn_twitch_client(app: &AppHandle, login: Option<String>, token: Option<String>) -> TwitchClient {
+    let config = if let (Some(u), Some(t)) = (login.clone(), token.clone()) {
         // Strip "oauth:" because twitch_irc might prepend it, or we want to normalize.
         // Sending "oauth:token" often results in "oauth:oauth:token" if the lib is helpful.
         // If the lib expects "oauth:token", passing "token" might fail, but let's try this common fix first.
@@ -76,18 +149,27 @@ pub async fn start_twitch_handler(
         ClientConfig::default()
     };
 
-    let (mut incoming_messages, client) =
-        TwitchIRCClient::<SecureTCPTransport, StaticLoginCredentials>::new(config);
+    let (mut incoming_messages, client) = TwitchClient::new(config);
 
     // Create shutdown channel
     let (tx, mut rx) = tokio::sync::broadcast::channel(1);
 
     // Store client and shutdown sender in state
-    if let Some(state) = app.try_state::<TwitchAppState>() {
-        *state.client.write().unwrap() = Some(client.clone());
-        *state.shutdown_tx.write().unwrap() = Some(tx);
-        if let Some(t) = token {
-             *state.access_token.write().unwrap() = Some(t.replace("oauth:", ""));
+    let state = app.state::<TwitchAppState>();
+    if let Some(old_tx) = state.shutdown_tx.write().unwrap().replace(tx) {
+        eprintln!("Replacing previous Twitch client...");
+        let _ = old_tx.send(());
+    }
+    *state.client.write().unwrap() = Some(client.clone());
+    *state.login.write().unwrap() = login;
+    if let Some(t) = token {
+         set_access_token(app, &t);
+    }
+
+    let rejoin: Vec<String> = state.channels.read().unwrap().keys().cloned().collect();
+    for channel in rejoin {
+        if let Err(e) = client.join(channel.clone()) {
+            eprintln!("Failed to re-join twitch channel {}: {}", channel, e);
         }
     }
 
@@ -107,130 +189,7 @@ pub async fn start_twitch_handler(
                 }
                 msg_opt = incoming_messages.recv() => {
                     match msg_opt {
-                        Some(message) => {
-                             // eprintln!("Twitch Raw Message: {:?}", message); // Disabled global logging to reduce noise
-                             if let ServerMessage::Privmsg(msg) = message {
-                                 // Emit channel ID just in case RoomState didn't catch it or we reconnected silently
-                                 // Emitting generic string is fine, frontend handles dedupe
-                                 // app_clone.emit("twitch-connected", msg.channel_id.clone()).unwrap_or(());
-                 
-                                 // eprintln!("Twitch msg received from: {}", msg.sender.name);
-                                 let is_mod = msg.badges.iter().any(|b| b.name == "moderator");
-                                 let is_vip = msg.badges.iter().any(|b| b.name == "vip");
-                                 
-                                 let emotes = msg.emotes.iter().map(|e| crate::models::Emote {
-                                     id: e.id.clone(),
-                                     code: e.code.clone(),
-                                     start: e.char_range.start,
-                                     end: e.char_range.end,
-                                 }).collect();
-                 
-                                 // Check for Bits
-                                 let bits_amount = msg.source.tags.0.get("bits").and_then(|s: &Option<String>| s.clone());
-                                 
-                                 // Check for Channel Point Redemption
-                                 // Twitch sends "custom-reward-id" tag if a reward was redeemed
-                                 let custom_reward_id = msg.source.tags.0.get("custom-reward-id").and_then(|s: &Option<String>| s.clone());
-                 
-                                 let mut system_message = None;
-                                 let mut msg_type = "chat".to_string();
-                 
-                                 if let Some(bits) = bits_amount {
-                                     system_message = Some(format!("Cheered {} Bits!", bits));
-                                     msg_type = "sub".to_string(); // Use "sub" style for now as it draws attention
-                                 } else if custom_reward_id.is_some() {
-                                      // We might get "msg-id": "highlighted-message" for "Highlight My Message" reward
-                                      // But custom-reward-id is generic for any reward.
-                                      // Often we don't have the reward NAME in the tags, just the ID.
-                                      // But we can genericize it:
-                                      system_message = Some("Redeemed a Channel Reward!".to_string());
-                                      msg_type = "sub".to_string();
-                                 }
-                 
-                                 let chat_message = ChatMessage {
-                                     id: msg.message_id,
-                                     platform: Platform::Twitch,
-                                     username: msg.sender.name,
-                                     message: msg.message_text,
-                                     color: msg.name_color.map(|c| format!("#{:02X}{:02X}{:02X}", c.r, c.g, c.b)),
-                                     badges: msg.badges.iter().map(|b| b.name.clone()).collect(),
-                                     is_mod,
-                                     is_vip,
-                                     is_member: false,
-                                     timestamp: chrono::Local::now().to_rfc3339(),
-                                     emotes,
-                                     msg_type,
-                                     system_message,
-                                 };
-                                 
-                                 if let Err(e) = app_clone.emit("chat-message", chat_message) {
-                                     eprintln!("Failed to emit twitch message: {}", e);
-                                 } else {
-                                     // eprintln!("Emitted twitch message");
-                                 }
-                             } else if let ServerMessage::Notice(msg) = message {
-                                  eprintln!("Twitch Notice: {}", msg.message_text);
-                                  if msg.message_text == "Login authentication failed" {
-                                      app_clone.emit("twitch-error", "Login authentication failed. Please check your token.").unwrap_or(());
-                                  }
-                             } else if let ServerMessage::UserState(msg) = message {
-                                 let is_mod = msg.badges.iter().any(|b| b.name == "moderator" || b.name == "broadcaster");
-                                 let badges: Vec<String> = msg.badges.iter().map(|b| b.name.clone()).collect();
-                                 eprintln!("Twitch UserState for me: is_mod={}, badges={:?}", is_mod, badges);
-                                 app_clone.emit("twitch-current-user-state", serde_json::json!({ "is_mod": is_mod, "badges": badges })).unwrap_or(());
-                 
-                             } else if let ServerMessage::GlobalUserState(msg) = message {
-                                  // Global state doesn't necessarily tell us channel mod status, but good to know identity
-                                  let badges: Vec<String> = msg.badges.iter().map(|b| b.name.clone()).collect();
-                                  eprintln!("Twitch GlobalUserState: badges={:?}", badges);
-                                  // Broadcaster badge might be here if we are the broadcaster connecting to our own channel
-                                  let is_mod = msg.badges.iter().any(|b| b.name == "broadcaster"); 
-                                  // Note: 'moderator' badge usually appears in UserState (per channel), not GlobalUserState
-                                  if is_mod {
-                                      app_clone.emit("twitch-current-user-state", serde_json::json!({ "is_mod": is_mod, "badges": badges })).unwrap_or(());
-                                  }
-                             } else if let ServerMessage::UserNotice(msg) = message {
-                                 // Handle Subs, Resubs, Raids, etc.
-                                 eprintln!("Twitch UserNotice: {:?}", msg);
-                                 
-                                 let system_msg = msg.system_message; 
-                                 let user_text = msg.message_text.unwrap_or_default();
-                                 let sender_name = msg.sender.name; // User who subbed
-                 
-                                 let emotes = msg.emotes.iter().map(|e| crate::models::Emote {
-                                     id: e.id.clone(),
-                                     code: e.code.clone(),
-                                     start: e.char_range.start,
-                                     end: e.char_range.end,
-                                 }).collect();
-                 
-                                 // Always emit UserNotice (Sub/Resub)
-                                  let chat_message = ChatMessage {
-                                     id: msg.source.tags.0.get("id").and_then(|s| s.clone()).unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
-                                     platform: Platform::Twitch,
-                                     username: sender_name,
-                                     message: user_text,
-                                     color: Some("#9146FF".to_string()), // Default system color, but maybe user color?
-                                     badges: vec![],
-                                     is_mod: false,
-                                     is_vip: false,
-                                     is_member: false,
-                                     timestamp: chrono::Local::now().to_rfc3339(),
-                                     emotes,
-                                     msg_type: "sub".to_string(),
-                                     system_message: Some(system_msg),
-                                 };
-                                 app_clone.emit("chat-message", chat_message).unwrap_or(());
-                             } else if let ServerMessage::RoomState(msg) = message {
-                                  // Emit channel ID for 3rd party emotes fetching
-                                  if let Some(room_id) = msg.source.tags.0.get("room-id").and_then(|s: &Option<String>| s.clone()) {
-                                      eprintln!("Twitch RoomState: room-id={}", room_id);
-                                      app_clone.emit("twitch-connected", room_id).unwrap_or(());
-                                  }
-                             } else if let ServerMessage::Join(msg) = message {
-                                  eprintln!("Twitch Joined: {}", msg.channel_login);
-                             }
-                        }
+                        Some(message) => handle_twitch_message(&app_clone, message),
                         None => {
                             eprintln!("Twitch incoming stream ended.");
                             break;
@@ -241,43 +200,249 @@ pub async fn start_twitch_handler(
         }
     });
 
-    // Handle channel name: Stripping hash and ensuring lowercase/trimmed
-    let channel_clean = channel.trim().trim_start_matches('#').to_lowercase();
-    
-    eprintln!("Attempting to join Twitch channel: {}", channel_clean);
-    
-    // Slight delay to ensure connection is established before joining?
-    // TwitchIRCClient buffers commands usually, so it should be fine.
-    if let Err(e) = client.join(channel_clean) {
-         eprintln!("Failed to join twitch channel: {}", e);
-    } else {
-         eprintln!("Joined Twitch channel request sent.");
+    client
+}
+
+fn twitch_source(channel_login: String, room_id: Option<String>) -> ChatSource {
+    ChatSource {
+        platform: Platform::Twitch,
+        display_name: Some(channel_login.clone()),
+        channel: channel_login,
+        room_id,
+    }
+}
+
+fn emit_moderation(app: &AppHandle, source: ChatSource, action: ModerationAction, timestamp: chrono::DateTime<chrono::Utc>) {
+    app.emit("chat-moderation", ModerationEvent { source, action, timestamp }).unwrap_or(());
+}
+
+fn user_notice_type(msg: &UserNoticeMessage) -> MessageType {
+    match &msg.event {
+        UserNoticeEvent::SubOrResub { is_resub: false, sub_plan, .. } => MessageType::Sub { tier: Some(sub_plan.clone()) },
+        UserNoticeEvent::SubOrResub { sub_plan, cumulative_months, streak_months, .. } => MessageType::Resub {
+            tier: Some(sub_plan.clone()),
+            months: *cumulative_months,
+            streak_months: *streak_months,
+        },
+        UserNoticeEvent::SubGift { is_sender_anonymous, recipient, sub_plan, num_gifted_months, .. } => MessageType::SubGift {
+            tier: Some(sub_plan.clone()),
+            count: 1,
+            recipient: Some(recipient.name.clone()),
+            gifted_months: Some(*num_gifted_months),
+            gifter_total: None,
+            anonymous: *is_sender_anonymous,
+        },
+        UserNoticeEvent::SubMysteryGift { mass_gift_count, sender_total_gifts, sub_plan } => MessageType::SubGift {
+            tier: Some(sub_plan.clone()),
+            count: *mass_gift_count,
+            recipient: None,
+            gifted_months: None,
+            gifter_total: Some(*sender_total_gifts),
+            anonymous: false,
+        },
+        UserNoticeEvent::AnonSubMysteryGift { mass_gift_count, sub_plan } => MessageType::SubGift {
+            tier: Some(sub_plan.clone()),
+            count: *mass_gift_count,
+            recipient: None,
+            gifted_months: None,
+            gifter_total: None,
+            anonymous: true,
+        },
+        UserNoticeEvent::GiftPaidUpgrade { gifter_name, .. } => MessageType::GiftPaidUpgrade { gifter: Some(gifter_name.clone()) },
+        UserNoticeEvent::AnonGiftPaidUpgrade { .. } => MessageType::GiftPaidUpgrade { gifter: None },
+        UserNoticeEvent::Raid { viewer_count, .. } => MessageType::Raid { viewer_count: *viewer_count },
+        UserNoticeEvent::BitsBadgeTier { threshold } => MessageType::BitsBadgeTier { threshold: *threshold },
+        UserNoticeEvent::Ritual { ritual_name } => MessageType::Ritual { name: ritual_name.clone() },
+        // twitch-irc has no typed variant for announcements; they arrive as msg-id=announcement
+        // with msg-param-color set to PRIMARY, BLUE, GREEN, ORANGE or PURPLE
+        _ if msg.event_id == "announcement" => MessageType::Announcement {
+            color: msg.source.tags.0.get("msg-param-color").and_then(|s| s.clone()),
+        },
+        _ => MessageType::System,
+    }
+}
+
+fn handle_twitch_message(app: &AppHandle, message: ServerMessage) {
+    // eprintln!("Twitch Raw Message: {:?}", message); // Disabled global logging to reduce noise
+    if let ServerMessage::Privmsg(msg) = message {
+        // eprintln!("Twitch msg received from: {}", msg.sender.name);
+        let is_mod = msg.badges.iter().any(|b| b.name == "moderator");
+        let is_vip = msg.badges.iter().any(|b| b.name == "vip");
+        
+        let emotes = msg.emotes.iter().map(|e| crate::models::Emote {
+            id: e.id.clone(),
+            code: e.code.clone(),
+            start: e.char_range.start,
+            end: e.char_range.end,
+        }).collect();
+
+        // Check for Channel Point Redemption
+        // Twitch sends "custom-reward-id" tag if a reward was redeemed
+        let custom_reward_id = msg.source.tags.0.get("custom-reward-id").and_then(|s: &Option<String>| s.clone());
+
+        let mut system_message = None;
+        let mut msg_type = if msg.is_action { MessageType::Action } else { MessageType::Chat };
+
+        if let Some(bits) = msg.bits {
+            system_message = Some(format!("Cheered {} Bits!", bits));
+            msg_type = MessageType::Cheer { bits };
+        } else if let Some(reward_id) = custom_reward_id {
+             // We might get "msg-id": "highlighted-message" for "Highlight My Message" reward
+             // But custom-reward-id is generic for any reward.
+             // Often we don't have the reward NAME in the tags, just the ID.
+             // But we can genericize it:
+             system_message = Some("Redeemed a Channel Reward!".to_string());
+             msg_type = MessageType::Redemption { reward_id };
+        }
+
+        let chat_message = ChatMessage {
+            id: msg.message_id,
+            platform: Platform::Twitch,
+            source: twitch_source(msg.channel_login, Some(msg.channel_id)),
+            username: msg.sender.name,
+            login: Some(msg.sender.login),
+            message: msg.message_text,
+            color: msg.name_color.map(|c| format!("#{:02X}{:02X}{:02X}", c.r, c.g, c.b)),
+            badges: msg.badges.iter().map(|b| b.name.clone()).collect(),
+            badge_images: HashMap::new(),
+            is_mod,
+            is_vip,
+            is_member: false,
+            timestamp: msg.server_timestamp,
+            received_at: chrono::Utc::now(),
+            emotes,
+            msg_type,
+            system_message,
+            video_offset_ms: None,
+            user_id: Some(msg.sender.id),
+            avatar_url: None,
+        };
+        
+        if let Err(e) = app.emit("chat-message", chat_message) {
+            eprintln!("Failed to emit twitch message: {}", e);
+        }
+    } else if let ServerMessage::Notice(msg) = message {
+         eprintln!("Twitch Notice: {}", msg.message_text);
+         if msg.message_text == "Login authentication failed" {
+             app.emit("twitch-error", "Login authentication failed. Please check your token.").unwrap_or(());
+         }
+    } else if let ServerMessage::UserState(msg) = message {
+        let is_mod = msg.badges.iter().any(|b| b.name == "moderator" || b.name == "broadcaster");
+        let badges: Vec<String> = msg.badges.iter().map(|b| b.name.clone()).collect();
+        eprintln!("Twitch UserState for me in {}: is_mod={}, badges={:?}", msg.channel_login, is_mod, badges);
+        if let Some(entry) = app.state::<TwitchAppState>().channels.write().unwrap().get_mut(&msg.channel_login) {
+            entry.is_mod = is_mod;
+            entry.badges = badges.clone();
+        }
+        app.emit("twitch-current-user-state", serde_json::json!({ "channel": msg.channel_login, "is_mod": is_mod, "badges": badges })).unwrap_or(());
+
+    } else if let ServerMessage::GlobalUserState(msg) = message {
+         // Global state doesn't necessarily tell us channel mod status, but good to know identity
+         let badges: Vec<String> = msg.badges.iter().map(|b| b.name.clone()).collect();
+         eprintln!("Twitch GlobalUserState: badges={:?}", badges);
+         // Broadcaster badge might be here if we are the broadcaster connecting to our own channel
+         let is_mod = msg.badges.iter().any(|b| b.name == "broadcaster"); 
+         // Note: 'moderator' badge usually appears in UserState (per channel), not GlobalUserState
+         if is_mod {
+             app.emit("twitch-current-user-state", serde_json::json!({ "is_mod": is_mod, "badges": badges })).unwrap_or(());
+         }
+    } else if let ServerMessage::UserNotice(msg) = message {
+        // Handle Subs, Resubs, Raids, etc.
+        let msg_type = user_notice_type(&msg);
+        eprintln!("Twitch UserNotice in {}: {} -> {:?}", msg.channel_login, msg.event_id, msg_type);
+
+        let system_msg = msg.system_message; 
+        let user_text = msg.message_text.unwrap_or_default();
+        let sender = msg.sender; // User who subbed
+
+        let emotes = msg.emotes.iter().map(|e| crate::models::Emote {
+            id: e.id.clone(),
+            code: e.code.clone(),
+            start: e.char_range.start,
+            end: e.char_range.end,
+        }).collect();
+
+        // Always emit UserNotice (Sub/Resub)
+         let chat_message = ChatMessage {
+            id: msg.source.tags.0.get("id").and_then(|s| s.clone()).unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+            platform: Platform::Twitch,
+            source: twitch_source(msg.channel_login, Some(msg.channel_id)),
+            username: sender.name,
+            login: Some(sender.login),
+            message: user_text,
+            color: Some("#9146FF".to_string()), // Default system color, but maybe user color?
+            badges: vec![],
+            badge_images: HashMap::new(),
+            is_mod: false,
+            is_vip: false,
+            is_member: false,
+            timestamp: msg.server_timestamp,
+            received_at: chrono::Utc::now(),
+            emotes,
+            msg_type,
+            system_message: Some(system_msg),
+            video_offset_ms: None,
+            user_id: Some(sender.id),
+            avatar_url: None,
+        };
+        app.emit("chat-message", chat_message).unwrap_or(());
+    } else if let ServerMessage::RoomState(msg) = message {
+         // Emit channel ID for 3rd party emotes fetching
+         eprintln!("Twitch RoomState: {} room-id={}", msg.channel_login, msg.channel_id);
+         if let Some(entry) = app.state::<TwitchAppState>().channels.write().unwrap().get_mut(&msg.channel_login) {
+             entry.room_id = Some(msg.channel_id.clone());
+         }
+         app.emit("twitch-connected", msg.channel_id).unwrap_or(());
+    } else if let ServerMessage::ClearChat(msg) = message {
+         let action = match msg.action {
+             ClearChatAction::ChatCleared => ModerationAction::ChatCleared,
+             ClearChatAction::UserBanned { user_login, user_id } => ModerationAction::UserBanned {
+                 username: user_login,
+                 user_id: Some(user_id),
+             },
+             ClearChatAction::UserTimedOut { user_login, user_id, timeout_length } => ModerationAction::UserTimedOut {
+                 username: user_login,
+                 user_id: Some(user_id),
+                 duration_secs: timeout_length.as_secs(),
+             },
+         };
+         eprintln!("Twitch ClearChat in {}: {:?}", msg.channel_login, action);
+         emit_moderation(app, twitch_source(msg.channel_login, Some(msg.channel_id)), action, msg.server_timestamp);
+    } else if let ServerMessage::ClearMsg(msg) = message {
+         // CLEARMSG carries no room-id tag, so take it from the channel registry
+         let room_id = app.state::<TwitchAppState>().channels.read().unwrap()
+             .get(&msg.channel_login)
+             .and_then(|c| c.room_id.clone());
+         eprintln!("Twitch ClearMsg in {}: {} by {}", msg.channel_login, msg.message_id, msg.sender_login);
+         emit_moderation(app, twitch_source(msg.channel_login, room_id), ModerationAction::MessageDeleted { message_id: msg.message_id }, msg.server_timestamp);
+    } else if let ServerMessage::Join(msg) = message {
+         eprintln!("Twitch Joined: {}", msg.channel_login);
+    } else if let ServerMessage::Part(msg) = message {
+         eprintln!("Twitch Parted: {}", msg.channel_login);
     }
 }
 
 pub async fn leave_twitch_channel(app: AppHandle, channel: String) {
     if let Some(state) = app.try_state::<TwitchAppState>() {
-        // 1. Send Shutdown Signal to Background Task
-        if let Some(tx) = state.shutdown_tx.read().unwrap().as_ref() {
-            eprintln!("Sending shutdown signal to Twitch handler...");
-            let _ = tx.send(());
-        }
+        let channel_clean = clean_channel(&channel);
 
-        // 2. Part from channel
-        // We need a read lock to get the client, but the client itself is thread-safe (clonable)
+        // 1. Part only the named channel; the shared client keeps serving the others
+        state.channels.write().unwrap().remove(&channel_clean);
         let client_opt = state.client.read().unwrap().clone();
-        
         if let Some(client) = client_opt {
-             let channel_clean = channel.trim().trim_start_matches('#').to_lowercase();
              eprintln!("Leaving Twitch channel: {}", channel_clean);
              client.part(channel_clean);
         }
-        
-        // 3. Clear State
-        *state.client.write().unwrap() = None;
-        *state.shutdown_tx.write().unwrap() = None;
-        // Optionally clear channel_id if we want full reset
-        // *state.channel_id.write().unwrap() = None;
+
+        // 2. Last channel gone: shut down the background task and drop the client
+        if state.channels.read().unwrap().is_empty() {
+            if let Some(tx) = state.shutdown_tx.write().unwrap().take() {
+                eprintln!("Sending shutdown signal to Twitch handler...");
+                let _ = tx.send(());
+            }
+            *state.client.write().unwrap() = None;
+            *state.login.write().unwrap() = None;
+        }
     }
 }
 
@@ -286,158 +451,162 @@ fn get_token(state: &TwitchAppState) -> Option<String> {
     state.access_token.read().unwrap().clone()
 }
 
+// Stores a user access token. A new token is validated in the background so Helix calls
+// don't have to wait for it, then re-validated hourly for as long as it stays current.
+fn set_access_token(app: &AppHandle, token: &str) {
+    let state = app.state::<TwitchAppState>();
+    let token = token.replace("oauth:", "");
+    let changed = state.access_token.read().unwrap().as_deref() != Some(token.as_str());
+    *state.access_token.write().unwrap() = Some(token.clone());
+    if changed {
+        *state.token.write().unwrap() = None;
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = refresh_twitch_token(&app).await {
+                eprintln!("Twitch token validation failed: {}", e);
+            }
+            loop {
+                tokio::time::sleep(TWITCH_TOKEN_REVALIDATE).await;
+                // A replaced or dropped token ends this loop; a new token starts its own
+                if get_token(&app.state::<TwitchAppState>()).as_deref() != Some(token.as_str()) {
+                    break;
+                }
+                if let Err(e) = refresh_twitch_token(&app).await {
+                    eprintln!("Hourly Twitch token validation failed: {}", e);
+                }
+            }
+        });
+    }
+}
+
+async fn validate_twitch_token(client: &reqwest::Client, token: &str) -> Result<Option<TwitchToken>, HeychatError> {
+    let res = client.get("https://id.twitch.tv/oauth2/validate")
+        .header("Authorization", format!("OAuth {}", token))
+        .send()
+        .await?;
+    if res.status() == reqwest::StatusCode::UNAUTHORIZED {
+        return Ok(None);
+    }
+    if !res.status().is_success() {
+        return Err(HeychatError::from_response(res).await);
+    }
+    Ok(Some(res.json::<TwitchToken>().await?))
+}
+
+// Validates the current access token and caches the result. An invalid token is dropped and
+// the frontend told, so the user can log in again.
+async fn refresh_twitch_token(app: &AppHandle) -> Result<TwitchToken, HeychatError> {
+    let state = app.state::<TwitchAppState>();
+    let token = get_token(&state).ok_or_else(not_logged_in)?;
+    let validated = validate_twitch_token(&state.api_client, &token).await?;
+
+    // The token may have been replaced while we were validating
+    if get_token(&state).as_deref() != Some(token.as_str()) {
+        return Err(HeychatError::Unauthorized("Twitch token changed during validation".to_string()));
+    }
+
+    match validated {
+        Some(info) => {
+            eprintln!("Twitch token valid for {:?}, expires {:?}", info.login, info.expires_at());
+            *state.token.write().unwrap() = Some(info.clone());
+            Ok(info)
+        }
+        None => {
+            eprintln!("Twitch token is no longer valid");
+            *state.token.write().unwrap() = None;
+            *state.access_token.write().unwrap() = None;
+            app.emit("twitch-token-invalid", ()).unwrap_or(());
+            Err(HeychatError::Unauthorized("Twitch token is invalid or expired".to_string()))
+        }
+    }
+}
+
+// The cached validation, validating now if the background check hasn't finished yet
+async fn twitch_token(app: &AppHandle) -> Result<TwitchToken, HeychatError> {
+    let state = app.state::<TwitchAppState>();
+    let cached = state.token.read().unwrap().clone();
+    match cached {
+        Some(info) if !info.is_expired(chrono::Utc::now()) => Ok(info),
+        _ => refresh_twitch_token(app).await,
+    }
+}
+
+// The cached validation, provided the token was granted `scope`
+async fn require_twitch_scope(app: &AppHandle, scope: &str) -> Result<TwitchToken, HeychatError> {
+    let info = twitch_token(app).await?;
+    if !info.scopes.iter().any(|s| s == scope) {
+        return Err(HeychatError::MissingScope(scope.to_string()));
+    }
+    Ok(info)
+}
+
+// Credentials for a Helix request made on behalf of the logged-in user
+fn helix_auth(app: &AppHandle, info: &TwitchToken) -> Result<HelixAuth, HeychatError> {
+    let token = get_token(&app.state::<TwitchAppState>()).ok_or_else(not_logged_in)?;
+    Ok(HelixAuth { token, client_id: info.client_id.clone() })
+}
+
+fn not_logged_in() -> HeychatError {
+    HeychatError::Unauthorized("Not logged in to Twitch".to_string())
+}
+
 #[tauri::command]
-pub async fn ensure_broadcaster_id(_app: AppHandle, state: tauri::State<'_, TwitchAppState>, username: String) -> Result<(), String> {
+pub async fn ensure_broadcaster_id(app: AppHandle, state: tauri::State<'_, TwitchAppState>, username: String) -> Result<(), HeychatError> {
     // Only fetch if we don't have it
     if state.channel_id.read().unwrap().is_some() {
         return Ok(());
     }
     
-    let token = get_token(&state).ok_or("No token found")?;
-    let client = &state.api_client;
-    let url = format!("https://api.twitch.tv/helix/users?login={}", username);
-    
-    // Validate first to get Client-Id
-    let validate_res = client.get("https://id.twitch.tv/oauth2/validate")
-        .header("Authorization", format!("OAuth {}", token))
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+    let auth = helix_auth(&app, &twitch_token(&app).await?)?;
+    let users = state.helix.get_users(&auth, &[&username], &[]).await?;
 
-    let json: serde_json::Value = validate_res.json().await.map_err(|e| e.to_string())?;
-    
-    if let Some(client_id) = json["client_id"].as_str() {
-            eprintln!("Obtained Client ID: {}", client_id);
-            
-            // Now we can fetch users with proper Client-ID
-            let res = client.get(&url)
-            .header("Authorization", format!("Bearer {}", token))
-            .header("Client-Id", client_id)
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
-
-            let user_res: HelixResponse<TwitchUser> = res.json().await.map_err(|e| e.to_string())?;
-            
-            if let Some(user) = user_res.data.first() {
-                *state.channel_id.write().unwrap() = Some(user.id.clone());
-                eprintln!("Fetched Twitch Broadcaster ID: {}", user.id);
-                Ok(())
-            } else {
-                Err("User not found".to_string())
-            }
+    if let Some(user) = users.first() {
+        *state.channel_id.write().unwrap() = Some(user.id.clone());
+        eprintln!("Fetched Twitch Broadcaster ID: {}", user.id);
+        Ok(())
     } else {
-        Err("Failed to extract client_id".to_string())
+        Err(HeychatError::NotFound(format!("Twitch user {} not found", username)))
     }
 }
 
 #[tauri::command]
 pub async fn twitch_ban_user(
-    _app: AppHandle,
-    state: tauri::State<'_, TwitchAppState>,
+    app: AppHandle,
     broadcaster_id: String,
-    moderator_id: String,
+    moderator_id: Option<String>, // Defaults to the logged-in user
     user_id: String,
     reason: String,
     duration: Option<u32> 
-) -> Result<(), String> {
-   let token = get_token(&state).ok_or("No Twitch token found")?;
-   let client = &state.api_client;
-
-   let validate_res = client.get("https://id.twitch.tv/oauth2/validate")
-        .header("Authorization", format!("OAuth {}", token))
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-
-   let json: serde_json::Value = validate_res.json().await.map_err(|e| e.to_string())?;
-   let client_id = json["client_id"].as_str().ok_or("Failed to get Client ID")?;
-
-   let url = "https://api.twitch.tv/helix/moderation/bans";
-   
-   // Helix Ban API structure
-   let data = if let Some(dur) = duration {
-       serde_json::json!({
-           "user_id": user_id,
-           "reason": reason,
-           "duration": dur
-       })
-   } else {
-       serde_json::json!({
-           "user_id": user_id,
-           "reason": reason
-       })
+) -> Result<(), HeychatError> {
+   let token_info = require_twitch_scope(&app, "moderator:manage:banned_users").await?;
+   let auth = helix_auth(&app, &token_info)?;
+   let moderator_id = match moderator_id {
+       Some(id) => id,
+       None => token_info.user_id.ok_or_else(|| HeychatError::Unauthorized("Twitch token has no user".to_string()))?,
    };
 
-   let body = serde_json::json!({ "data": data });
-
-   let res = client.post(url)
-       .header("Authorization", format!("Bearer {}", token))
-       .header("Client-Id", client_id)
-       .query(&[("broadcaster_id", &broadcaster_id), ("moderator_id", &moderator_id)])
-       .json(&body)
-       .send()
-       .await
-       .map_err(|e| e.to_string())?;
-
-   if res.status().is_success() {
-       Ok(())
-   } else {
-       let serr = res.text().await.unwrap_or_default();
-       Err(format!("Failed to ban/timeout: {}", serr))
-   }
+   let ban = helix::BanUserRequest { user_id, reason, duration };
+   Ok(app.state::<TwitchAppState>().helix.ban_user(&auth, &broadcaster_id, &moderator_id, ban).await?)
 }
 
 #[tauri::command]
 pub async fn twitch_create_poll(
-    _app: AppHandle,
-    state: tauri::State<'_, TwitchAppState>,
+    app: AppHandle,
     broadcaster_id: String,
     title: String,
     choices: Vec<String>,
     duration: u32
-) -> Result<(), String> {
-    let token = get_token(&state).ok_or("No Twitch token found")?;
-    let client = &state.api_client;
-    
-    let validate_res = client.get("https://id.twitch.tv/oauth2/validate")
-        .header("Authorization", format!("OAuth {}", token))
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-    let json: serde_json::Value = validate_res.json().await.map_err(|e| e.to_string())?;
-    let client_id = json["client_id"].as_str().ok_or("Failed to get Client ID")?;
-
-    let url = "https://api.twitch.tv/helix/polls";
-    let choices_json: Vec<_> = choices.iter().map(|c| serde_json::json!({"title": c})).collect();
-    
-    let body = serde_json::json!({
-        "broadcaster_id": broadcaster_id,
-        "title": title,
-        "choices": choices_json,
-        "duration": duration
-    });
-
-    let res = client.post(url)
-        .header("Authorization", format!("Bearer {}", token))
-        .header("Client-Id", client_id)
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    if res.status().is_success() {
-        Ok(())
-    } else {
-        let err_text = res.text().await.unwrap_or_default();
-        Err(format!("Failed to create poll: {}", err_text))
-    }
+) -> Result<(), HeychatError> {
+    let auth = helix_auth(&app, &require_twitch_scope(&app, "channel:manage:polls").await?)?;
+    let poll = helix::CreatePollRequest {
+        broadcaster_id,
+        title,
+        choices: choices.into_iter().map(|title| helix::Choice { title }).collect(),
+        duration,
+    };
+    Ok(app.state::<TwitchAppState>().helix.create_poll(&auth, poll).await?)
 }
 
-
-
-
-
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TwitchUserCardData {
     pub display_name: String,
@@ -451,105 +620,29 @@ pub struct TwitchUserCardData {
 
 #[tauri::command]
 pub async fn twitch_get_user_card_data(
-    _app: AppHandle, 
+    app: AppHandle, 
     state: tauri::State<'_, TwitchAppState>, 
     broadcaster_id: String, 
     target_username: String
-) -> Result<TwitchUserCardData, String> {
-    let token = get_token(&state).ok_or("No Twitch token found")?;
+) -> Result<TwitchUserCardData, HeychatError> {
     let client = &state.api_client;
-    
-    // 1. Get Logged In User Info (Me) - Async but we need ID for subsequent calls?
-    // Actually, we can start fetching "Me" and "User" in parallel.
-    
-    // We can't easily parallelize validating + getting ID without refactoring too much, 
-    // but we can parallelize:
-    // A. Verify Token -> Get Client ID
-    // B. Get Me (Parallel)
-    // C. Get Target User (Parallel) 
-    // D. Get Broadcaster Login (if needed) - (Parallel or depend on input)
-    
-    // Let's keep it simple but improved:
-    // 0. Validate (Fast)
-    let validate_res = client.get("https://id.twitch.tv/oauth2/validate")
-         .header("Authorization", format!("OAuth {}", token))
-         .send()
-         .await
-         .map_err(|e| e.to_string())?;
-
-    let json: serde_json::Value = validate_res.json().await.map_err(|e| e.to_string())?;
-    let client_id = json["client_id"].as_str().ok_or("Failed to get Client ID")?;
-
-    // Now spawn parallel requests
-    let client_clone1 = client.clone();
-    let client_clone2 = client.clone();
-    let client_clone3 = client.clone();
-    let token_clone1 = token.clone();
-    let token_clone2 = token.clone();
-    let token_clone3 = token.clone();
-    let client_id_own = client_id.to_string(); // Owned string for moves
-    let client_id_own2 = client_id.to_string();
-    let client_id_own3 = client_id.to_string();
-    let target_username_clone = target_username.clone();
-    let broadcaster_id_clone = broadcaster_id.clone();
-
-    // Future A: Get Me
-    let me_future = tokio::spawn(async move {
-        let me_res = client_clone1.get("https://api.twitch.tv/helix/users")
-            .header("Authorization", format!("Bearer {}", token_clone1))
-            .header("Client-Id", client_id_own)
-            .send()
-            .await;
-        match me_res {
-             Ok(res) => res.json::<HelixResponse<TwitchUser>>().await.map_err(|e| e.to_string()),
-             Err(e) => Err(e.to_string())
-        }
-    });
-
-    // Future B: Get Target User
-    let user_future = tokio::spawn(async move {
-        let user_url = format!("https://api.twitch.tv/helix/users?login={}", target_username_clone);
-        let user_res = client_clone2.get(&user_url)
-            .header("Authorization", format!("Bearer {}", token_clone2))
-            .header("Client-Id", client_id_own2)
-            .send()
-            .await;
-        match user_res {
-            Ok(res) => res.json::<HelixResponse<TwitchUser>>().await.map_err(|e| e.to_string()),
-            Err(e) => Err(e.to_string())
-        }
-    });
-
-    // Future C: Get Broadcaster Login (for IVR)
-    let channel_future = tokio::spawn(async move {
-         let channel_res = client_clone3.get("https://api.twitch.tv/helix/users")
-            .header("Authorization", format!("Bearer {}", token_clone3))
-            .header("Client-Id", client_id_own3)
-            .query(&[("id", &broadcaster_id_clone)])
-            .send()
-            .await;
-         match channel_res {
-             Ok(res) => res.json::<HelixResponse<TwitchUser>>().await.map_err(|e| e.to_string()),
-             Err(e) => Err(e.to_string())
-         }
-    });
-
-    // Await A & B & C
-    let (me_result, user_result, channel_result) = tokio::join!(me_future, user_future, channel_future);
 
-    // Unwrap JoinErrors (panics) then Result strings
-    let me_data = me_result.map_err(|_| "Join Error Me".to_string())??;
-    let user_data = user_result.map_err(|_| "Join Error User".to_string())??;
-    let channel_data = channel_result.map_err(|_| "Join Error Channel".to_string())??;
+    let auth = helix_auth(&app, &twitch_token(&app).await?)?;
 
-    let me = me_data.data.first().ok_or("Failed to fetch my user info")?;
-    let _my_id = me.id.clone();
+    // Target user and broadcaster login (for IVR) in parallel
+    let (logins, ids) = ([target_username.as_str()], [broadcaster_id.as_str()]);
+    let (user_data, channel_data) = tokio::join!(
+        state.helix.get_users(&auth, &logins, &[]),
+        state.helix.get_users(&auth, &[], &ids)
+    );
+    let user_data = user_data?;
+    let channel_data = channel_data?;
 
-    let user = user_data.data.first().ok_or("User not found")?;
+    let user = user_data.first().ok_or_else(|| HeychatError::NotFound(format!("Twitch user {} not found", target_username)))?;
     
-    let channel_login = channel_data.data.first()
+    let channel_login = channel_data.first()
         .map(|u| u.login.clone())
-        .ok_or("Broadcaster not found")?;
+        .ok_or_else(|| HeychatError::NotFound("Broadcaster not found".to_string()))?;
 
     // Now IVR call
     let ivr_url = format!("https://api.ivr.fi/v2/twitch/subage/{}/{}", user.login, channel_login);
@@ -599,132 +692,47 @@ pub async fn twitch_get_user_card_data(
 
 #[tauri::command]
 pub async fn twitch_create_prediction(
-    _app: AppHandle,
-    state: tauri::State<'_, TwitchAppState>,
+    app: AppHandle,
     broadcaster_id: String,
     title: String,
     outcomes: Vec<String>,
     prediction_window: u32
-) -> Result<(), String> {
-    let token = get_token(&state).ok_or("No Twitch token found")?;
-    let client = &state.api_client;
-    
-    let validate_res = client.get("https://id.twitch.tv/oauth2/validate")
-        .header("Authorization", format!("OAuth {}", token))
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-    let json: serde_json::Value = validate_res.json().await.map_err(|e| e.to_string())?;
-    let client_id = json["client_id"].as_str().ok_or("Failed to get Client ID")?;
-
-    let url = "https://api.twitch.tv/helix/predictions";
-    let outcomes_json: Vec<_> = outcomes.iter().map(|c| serde_json::json!({"title": c})).collect();
-    
-    let body = serde_json::json!({
-        "broadcaster_id": broadcaster_id,
-        "title": title,
-        "outcomes": outcomes_json,
-        "prediction_window": prediction_window
-    });
-
-    let res = client.post(url)
-        .header("Authorization", format!("Bearer {}", token))
-        .header("Client-Id", client_id)
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    if res.status().is_success() {
-        Ok(())
-    } else {
-         let err_text = res.text().await.unwrap_or_default();
-        Err(format!("Failed to create prediction: {}", err_text))
-    }
-}
-
-
-#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
-pub struct TwitchEmote {
-    pub id: String,
-    pub name: String,
-    pub emote_type: Option<String>, 
-    pub emote_set_id: Option<String>,
-    pub owner_id: Option<String>,
-    pub format: Vec<String>,
-    pub scale: Vec<String>,
-    pub theme_mode: Vec<String>,
-    pub category: Option<String>, // "global", "channel", "user"
-    #[serde(default)]
-    pub locked: bool,
+) -> Result<(), HeychatError> {
+    let auth = helix_auth(&app, &require_twitch_scope(&app, "channel:manage:predictions").await?)?;
+    let prediction = helix::CreatePredictionRequest {
+        broadcaster_id,
+        title,
+        outcomes: outcomes.into_iter().map(|title| helix::Choice { title }).collect(),
+        prediction_window,
+    };
+    Ok(app.state::<TwitchAppState>().helix.create_prediction(&auth, prediction).await?)
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct UserEmotesResponse {
-    pub data: Vec<TwitchEmote>,
-    // template and pagination ignored
-}
 
 #[tauri::command]
 pub async fn twitch_get_user_emotes(
-    _app: AppHandle,
-    state: tauri::State<'_, TwitchAppState>,
+    app: AppHandle,
     broadcaster_id: String
-) -> Result<Vec<TwitchEmote>, String> {
-    let token = get_token(&state).ok_or("No Twitch token found")?;
-    let client = &state.api_client;
-    
-    // 1. Get Client ID (needed for all requests)
-    let validate_res = client.get("https://id.twitch.tv/oauth2/validate")
-        .header("Authorization", format!("OAuth {}", token))
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-    let json: serde_json::Value = validate_res.json().await.map_err(|e| e.to_string())?;
-    let client_id = json["client_id"].as_str().ok_or("Failed to get Client ID")?;
-    let user_id = json["user_id"].as_str().unwrap_or(&broadcaster_id);
-
-    // Helpers
-    let get_emotes = |url: String| {
-        let client = client.clone();
-        let token = token.clone();
-        let client_id = client_id.to_string();
-        async move {
-            let res = client.get(&url)
-                .header("Authorization", format!("Bearer {}", token))
-                .header("Client-Id", client_id)
-                .send()
-                .await
-                .map_err(|e| e.to_string())?;
-            
-            if res.status().is_success() {
-                let body = res.text().await.map_err(|e| e.to_string())?;
-                let response: UserEmotesResponse = serde_json::from_str(&body).map_err(|e| e.to_string())?;
-                Ok(response.data)
-            } else {
-                 Err(format!("Req Failed: {}", res.status()))
-            }
-        }
-    };
+) -> Result<Vec<helix::Emote>, HeychatError> {
+    let token_info = twitch_token(&app).await?;
+    let auth = helix_auth(&app, &token_info)?;
+    let user_id = token_info.user_id.unwrap_or_else(|| broadcaster_id.clone());
+    let state = app.state::<TwitchAppState>();
 
     // 2. Fetch All 3 Sources Concurrent-ish
-    // A. User Emotes (Subs, Follows, Bit tiers) - These are UNLOCKED
-    let url_user = format!("https://api.twitch.tv/helix/chat/emotes/user?user_id={}", user_id);
+    // A. User Emotes (Subs, Follows, Bit tiers) - These are UNLOCKED. Paginated, every page is fetched.
     // B. Global Emotes - These are UNLOCKED
-    let url_global = "https://api.twitch.tv/helix/chat/emotes/global".to_string();
     // C. Channel Emotes - These include LOCKED ones if not subbed
-    let url_channel = format!("https://api.twitch.tv/helix/chat/emotes?broadcaster_id={}", broadcaster_id);
-
     let (r_user, r_global, r_channel) = tokio::join!(
-        get_emotes(url_user),
-        get_emotes(url_global),
-        get_emotes(url_channel)
+        state.helix.get_user_emotes(&auth, &user_id),
+        state.helix.get_global_emotes(&auth),
+        state.helix.get_channel_emotes(&auth, &broadcaster_id)
     );
 
     let mut all_emotes = Vec::new();
     let mut seen_ids = std::collections::HashSet::new();
 
-    let mut process_list = |list: Result<Vec<TwitchEmote>, String>, category: &str, label: &str, is_locked_fallback: bool| {
+    let mut process_list = |list: Result<Vec<helix::Emote>, helix::HelixError>, category: &str, label: &str, is_locked_fallback: bool| {
         match list {
             Ok(emotes) => {
                 for mut e in emotes {
@@ -742,7 +750,7 @@ pub async fn twitch_get_user_emotes(
                     }
                 }
             },
-            Err(e) => println!("Failed to load {}: {}", label, e),
+            Err(e) => eprintln!("Failed to load {} emotes: {}", label, e),
         }
     };
     
@@ -780,3 +788,34 @@ pub async fn twitch_get_user_emotes(
 
     Ok(all_emotes)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(expires_in: u64) -> TwitchToken {
+        TwitchToken {
+            client_id: "client".to_string(),
+            user_id: Some("1".to_string()),
+            login: Some("user".to_string()),
+            scopes: vec![],
+            expires_in,
+            validated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn token_expires_after_expires_in() {
+        let info = token(60);
+        assert_eq!(info.expires_at(), Some(info.validated_at + chrono::Duration::seconds(60)));
+        assert!(!info.is_expired(info.validated_at + chrono::Duration::seconds(59)));
+        assert!(info.is_expired(info.validated_at + chrono::Duration::seconds(60)));
+    }
+
+    #[test]
+    fn token_with_zero_expires_in_never_expires() {
+        let info = token(0);
+        assert_eq!(info.expires_at(), None);
+        assert!(!info.is_expired(info.validated_at + chrono::Duration::days(365)));
+    }
+}
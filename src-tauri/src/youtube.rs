@@ -1,37 +1,66 @@
-use crate::models::{ChatMessage, Platform};
+use crate::error::HeychatError;
+use crate::models::{ChatMessage, ChatSource, Emote, MessageType, ModerationAction, ModerationEvent, Platform};
+use crate::youtube_bootstrap::{ChatMode, YouTubeBootstrap};
 use reqwest::Client;
 use serde_json::Value;
 use tauri::{AppHandle, Emitter, Manager};
 use regex::Regex;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{LazyLock, Mutex};
+use rand::{Rng, thread_rng};
+use sha1::{Digest, Sha1};
+
+// Poll as often as the server asks (timeoutMs), within sane bounds; 1s when it doesn't say
+const YOUTUBE_DEFAULT_POLL: Duration = Duration::from_secs(1);
+const YOUTUBE_MIN_POLL_MS: u64 = 500;
+const YOUTUBE_MAX_POLL_MS: u64 = 30_000;
+
+// Error backoff: 2s doubling up to two minutes, plus up to 50% random jitter
+const YOUTUBE_BACKOFF_BASE_MS: u64 = 2_000;
+const YOUTUBE_BACKOFF_MAX_MS: u64 = 120_000;
+const YOUTUBE_MAX_CONSECUTIVE_ERRORS: u32 = 10;
+
+// Origin the web chat signs its innertube requests for
+const YOUTUBE_ORIGIN: &str = "https://www.youtube.com";
+
+const REPLAY_MIN_SPEED: f64 = 0.25;
+const REPLAY_MAX_SPEED: f64 = 8.0;
+// Shortest wait before the replay fetches again, so empty batches can't spin the loop
+const REPLAY_MIN_REFETCH: Duration = Duration::from_millis(YOUTUBE_MIN_POLL_MS);
+
+// How to play back the chat of a finished stream
+#[derive(serde::Deserialize, Debug, Default)]
+pub struct ReplayOptions {
+    #[serde(default)]
+    pub start_offset_ms: u64,
+    pub speed: Option<f64>, // 1.0 is real time
+}
+
+// Controls for a running replay, sent from the frontend
+#[derive(serde::Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReplayCommand {
+    Pause,
+    Resume,
+    SetSpeed { speed: f64 },
+    Seek { offset_ms: u64 },
+}
+
+// Handle to a running live chat poller
+pub struct YouTubePoller {
+    pub shutdown_tx: tokio::sync::broadcast::Sender<()>,
+    pub commands_tx: tokio::sync::mpsc::UnboundedSender<ReplayCommand>,
+}
 
 // Use a State struct for shutdown management
+// Map<VideoId, YouTubePoller>
 pub struct YouTubeState {
-    pub shutdown_tx: std::sync::RwLock<Option<tokio::sync::broadcast::Sender<()>>>,
+    pub pollers: std::sync::RwLock<HashMap<String, YouTubePoller>>,
+    pub send_sessions: std::sync::RwLock<HashMap<String, YouTubeSendSession>>, // Map<VideoId, YouTubeSendSession>
 }
 
-pub async fn start_youtube_handler(app: AppHandle, video_id: String) {
-    let app_clone = app.clone();
-    
-    // Check if we need to shut down previous instance? Or assumes UI handles it.
-    // Ideally we should store shutdown handle.
-    let state = app.state::<YouTubeState>();
-    
-    // Create new shutdown channel
-    let (tx, mut rx) = tokio::sync::broadcast::channel(1);
-    
-    {
-        // Cancel previous if exists? Or just overwrite?
-        // If we overwrite, previous one might run forever if it didn't get signal.
-        // We should send signal first if it exists.
-        let mut guard = state.shutdown_tx.write().unwrap();
-        if let Some(old_tx) = guard.take() {
-             eprintln!("Youtube Handler: Stopping previous instance...");
-             let _ = old_tx.send(());
-        }
-        *guard = Some(tx);
-    }
-
+pub async fn start_youtube_handler(app: AppHandle, video_id: String, mode: ChatMode, replay: ReplayOptions) {
     // 1. Extract Video ID from URL if needed
     let client = Client::builder()
         .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36")
@@ -113,9 +142,39 @@ pub async fn start_youtube_handler(app: AppHandle, video_id: String) {
         candidate_input
     };
 
+    // Register the poller; re-joining a video that is already running replaces its poller
+    let (tx, rx) = tokio::sync::broadcast::channel(1);
+    let (commands_tx, commands_rx) = tokio::sync::mpsc::unbounded_channel();
+    {
+        let state = app.state::<YouTubeState>();
+        let mut pollers = state.pollers.write().unwrap();
+        if let Some(old) = pollers.insert(video_id_clean.clone(), YouTubePoller { shutdown_tx: tx.clone(), commands_tx }) {
+             eprintln!("Youtube Handler: Stopping previous instance for {}...", video_id_clean);
+             let _ = old.shutdown_tx.send(());
+        }
+    }
+
     eprintln!("Starting YouTube chat for video: {}", video_id_clean);
-    app_clone.emit("youtube-connected", &video_id_clean).unwrap_or(());
+    app.emit("youtube-connected", &video_id_clean).unwrap_or(());
+
+    poll_live_chat(&app, &video_id_clean, mode, replay, rx, commands_rx).await;
+
+    // Deregister, unless a newer poller for the same video has taken our slot
+    let state = app.state::<YouTubeState>();
+    let mut pollers = state.pollers.write().unwrap();
+    if pollers.get(&video_id_clean).is_some_and(|p| p.shutdown_tx.same_channel(&tx)) {
+        pollers.remove(&video_id_clean);
+    }
+}
 
+async fn poll_live_chat(
+    app: &AppHandle,
+    video_id_clean: &str,
+    requested_mode: ChatMode,
+    replay: ReplayOptions,
+    mut rx: tokio::sync::broadcast::Receiver<()>,
+    mut commands_rx: tokio::sync::mpsc::UnboundedReceiver<ReplayCommand>,
+) {
     // Reuse client? Or just make new one. The simple polling logic makes a new one.
     // But we already have one. Let's reuse 'client' if possible, or just shadow it/ignore.
     // The strict client above was for resolution.
@@ -135,240 +194,636 @@ pub async fn start_youtube_handler(app: AppHandle, video_id: String) {
     // ... (rest of function) ...
 
 
-    // 3. Extract API Key
-    let api_key_regex = Regex::new(r#""INNERTUBE_API_KEY":"([^"]+)""#).unwrap();
-    let api_key = match api_key_regex.captures(&html) {
-        Some(caps) => caps.get(1).map_or("", |m| m.as_str()).to_string(),
-        None => {
-            eprintln!("Could not find INNERTUBE_API_KEY. Is the video live?");
-            // Log a snippet of HTML for debugging if needed, or just return
-             eprintln!("HTML Snippet: {}", &html[0..500.min(html.len())]);
+    let source = ChatSource {
+        platform: Platform::YouTube,
+        channel: video_id_clean.to_string(),
+        room_id: None,
+        display_name: extract_owner_channel_name(&html),
+    };
+
+    // 3. Read the innertube config and chat continuation from the page's bootstrap JSON
+    let bootstrap = match YouTubeBootstrap::parse(&html) {
+        Ok(bootstrap) => bootstrap,
+        Err(e) => {
+            eprintln!("Could not read YouTube page data: {}. Is the video live?", e);
             return;
         }
     };
 
-    // 4. Extract Continuation Token
-    // Usually in "continuation":"..." inside "liveChatRenderer"
-    // Since parsing the huge JSON is hard with regex, we try to find the specific live chat continuation
-    // Pattern: "continuation":"<token>"
-    // But there are many continuations. We want the one for live chat.
-    // Ideally parse ytInitialData.
-    let continuation_regex = Regex::new(r#""continuation":"([^"]+)""#).unwrap();
-    // We search the HTML. It's risky but often works for the *first* one if it's a live video.
-    // Better: look for "liveChatRenderer".
-    
-    let mut continuation = String::new();
-    
-    if let Some(pos) = html.find("liveChatRenderer") {
-        let substring = &html[pos..];
-        if let Some(caps) = continuation_regex.captures(substring) {
-            continuation = caps.get(1).map_or("", |m| m.as_str()).to_string();
-        }
+    // 4. Pick the requested feed. Streams without a view selector only offer Top chat,
+    // so tell the frontend which one we ended up with.
+    let Some((mode, initial_continuation)) = bootstrap.select_continuation(requested_mode) else {
+        eprintln!("Could not find initial continuation token. Stream might be offline or no chat.");
+        return;
+    };
+    if mode != requested_mode {
+        eprintln!("{:?} chat unavailable for {}, using {:?} chat", requested_mode, video_id_clean, mode);
     }
+    app.emit("youtube-chat-mode", serde_json::json!({ "video_id": video_id_clean, "requested": requested_mode, "mode": mode })).unwrap_or(());
 
-    if continuation.is_empty() {
-         eprintln!("Could not find initial continuation token. Stream might be offline or no chat.");
-         // Try one generic search
-         if let Some(caps) = continuation_regex.captures(&html) {
-             continuation = caps.get(1).map_or("", |m| m.as_str()).to_string();
-             eprintln!("Found a continuation, trying it: {}", continuation);
-         } else {
-             return;
-         }
+    // Finished streams (VODs, premieres) only have a replay of their chat
+    if bootstrap.is_replay {
+        eprintln!("Replaying chat for {} from {}ms", video_id_clean, replay.start_offset_ms);
+        if let Some(reason) = replay_live_chat(app, &bootstrap, &source, initial_continuation, replay, &mut rx, &mut commands_rx).await {
+            emit_chat_ended(app, video_id_clean, reason);
+        }
+        return;
     }
+    // Live chat takes no replay commands; closing the receiver lets control_youtube_replay say so
+    drop(commands_rx);
 
-    eprintln!("Found API Key: {}...", &api_key[0..5]);
-    eprintln!("Initial Continuation: {}...", &continuation[0..10]);
+    let mut continuation = initial_continuation.to_string();
+
+    eprintln!("Found API Key: {}... (client {} {})", bootstrap.api_key.chars().take(5).collect::<String>(), bootstrap.client_name, bootstrap.client_version);
+    eprintln!("Initial Continuation: {}...", continuation.chars().take(10).collect::<String>());
 
     // 5. Polling Loop
     // Filter out messages older than connection time
     let start_time = std::time::SystemTime::now();
+
+    // Author-level removals only name the channel id; remember who that was
+    // Map<AuthorChannelId, AuthorName>
+    let mut authors: HashMap<String, String> = HashMap::new();
     
-    let mut polling = true;
-    while polling {
-        // Shutdown check before poll
-        if rx.try_recv().is_ok() {
-             eprintln!("YouTube handler received shutdown.");
-             break;
-        }
+    let chat_url = format!("https://www.youtube.com/youtubei/v1/live_chat/get_live_chat?key={}", bootstrap.api_key);
+    let since_usec = start_time.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_micros() as u64;
+    let mut errors: u32 = 0;
 
-        let chat_url = format!("https://www.youtube.com/youtubei/v1/live_chat/get_live_chat?key={}", api_key);
-        
+    let reason = loop {
         let body = serde_json::json!({
-            "context": {
-                "client": {
-                    "clientName": "WEB",
-                    "clientVersion": "2.20230622.06.00" // A somewhat recent version
-                }
-            },
+            "context": bootstrap.context(),
             "continuation": continuation
         });
 
-        match client.post(&chat_url).json(&body).send().await {
-            Ok(resp) => {
-                if let Ok(json) = resp.json::<Value>().await {
-                    // Extract actions
-                    if let Some(actions) = json.pointer("/continuationContents/liveChatContinuation/actions").and_then(|v| v.as_array()) {
-                        for action in actions {
-                            if let Some(item) = action.pointer("/addChatItemAction/item/liveChatTextMessageRenderer") {
-                                // Parse Timestamp first to filter history
-                                let timestamp_usec_str = item["timestampUsec"].as_str().unwrap_or("0");
-                                let timestamp_usec: u64 = timestamp_usec_str.parse().unwrap_or(0);
-                                let timestamp_micros = start_time.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_micros() as u64;
-                                
-                                // Buffer of 10 seconds? Or strict? 
-                                // If message is older than start_time, ignore it.
-                                // NOTE: YouTube sends history batch. We want to skip it.
-                                if timestamp_usec < timestamp_micros {
-                                    continue;
-                                }
-
-                                // Parse Message
-                                let id = item["id"].as_str().unwrap_or("").to_string();
-                                let author_name = item.pointer("/authorName/simpleText").and_then(|v| v.as_str()).unwrap_or("Unknown").to_string();
-                                
-                                let mut message_text = String::new();
-                                let mut emotes = Vec::new();
-
-                                if let Some(runs) = item.pointer("/message/runs").and_then(|v| v.as_array()) {
-                                    for run in runs {
-                                        if let Some(text) = run["text"].as_str() {
-                                            message_text.push_str(text);
-                                        } else if let Some(emoji_node) = run.pointer("/emoji") {
-                                            // Handle Emoji
-                                            let mut emoji_text = "".to_string();
-                                            
-                                            // 1. Try to find a shortcut (e.g. :cat:) to use as text representation
-                                            if let Some(shortcuts) = emoji_node.pointer("/shortcuts").and_then(|v| v.as_array()) {
-                                                if let Some(first) = shortcuts.first().and_then(|v| v.as_str()) {
-                                                    emoji_text = first.to_string();
-                                                }
-                                            }
-                                            
-                                            // Fallback to emojiId if no shortcut
-                                            if emoji_text.is_empty() {
-                                                if let Some(id) = emoji_node["emojiId"].as_str() {
-                                                    emoji_text = id.to_string();
-                                                }
-                                            }
-
-                                            // 2. Get the Image URL
-                                            let mut image_url = "".to_string();
-                                            if let Some(thumbnails) = emoji_node.pointer("/image/thumbnails").and_then(|v| v.as_array()) {
-                                                 // Usually the last one is biggest? Or first? checking 0 is usually fine for chat
-                                                 if let Some(url) = thumbnails.first().and_then(|t| t["url"].as_str()) {
-                                                     image_url = url.to_string();
-                                                 }
-                                            }
-
-                                            if !emoji_text.is_empty() {
-                                                // Frontend/JS usually uses simple string index (UTF-16) or similar.
-                                                // Rust string len is bytes.
-                                                // For simple emojis and text, we will use bytes for now as a proxy.
-                                                let start_index = message_text.len(); 
-                                                
-                                                message_text.push_str(&emoji_text);
-                                                
-                                                if !image_url.is_empty() {
-                                                    use crate::models::Emote;
-                                                    emotes.push(Emote {
-                                                        id: image_url,
-                                                        code: emoji_text.clone(),
-                                                        start: start_index, // This is technically BYTE offset. 
-                                                        end: start_index + emoji_text.len() - 1,
-                                                    });
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                                
-                                let mut is_mod = false;
-                                let mut is_member = false;
-                                
-                                if let Some(badges) = item.pointer("/authorBadges").and_then(|v| v.as_array()) {
-                                    for badge in badges {
-                                        if let Some(tooltip) = badge.pointer("/liveChatAuthorBadgeRenderer/tooltip").and_then(|v| v.as_str()) {
-                                            if tooltip.contains("Moderator") {
-                                                is_mod = true;
-                                            } else if tooltip.contains("Member") {
-                                                is_member = true;
-                                            }
-                                        }
-                                    }
-                                }
-                                
-                                let is_vip = false; 
-
-                                let mut color = None;
-                                if is_mod {
-                                    color = Some("#5e84f1".to_string()); // YouTube Mod Blue
-                                } else if is_member {
-                                    color = Some("#0f9d58".to_string()); // YouTube Member Green
-                                }
-                                
-                                let chat_message = ChatMessage {
-                                    id,
-                                    platform: Platform::YouTube,
-                                    username: author_name,
-                                    message: message_text,
-                                    color,
-                                    badges: vec![],
-                                    is_mod, 
-                                    is_vip,
-                                    is_member,
-                                    timestamp: chrono::Local::now().to_rfc3339(),
-                                    emotes,
-                                    msg_type: "chat".to_string(),
-                                    system_message: None,
-                                };
-                                
-                                app_clone.emit("chat-message", chat_message).unwrap_or(());
-                            }
-                        }
-                    } else {
-                        // eprintln!("No actions in response");
-                    }
-
-                    // Update Continuation
-                    if let Some(new_cont) = json.pointer("/continuationContents/liveChatContinuation/continuations/0/invalidationContinuationData/continuation")
-                        .or_else(|| json.pointer("/continuationContents/liveChatContinuation/continuations/0/timedContinuationData/continuation"))
-                        .and_then(|v| v.as_str()) 
-                    {
-                        continuation = new_cont.to_string();
-                    } else {
-                         eprintln!("No next continuation found. Stopping.");
-                         polling = false;
-                    }
-                } else {
-                     eprintln!("Failed to parse JSON");
+        let delay = match innertube_post(&client, &chat_url, &body).await {
+            Ok(json) => {
+                errors = 0;
+                let actions = json.pointer("/continuationContents/liveChatContinuation/actions").and_then(|v| v.as_array());
+                for action in actions.into_iter().flatten() {
+                    handle_chat_action(app, action, &source, &mut authors, since_usec, None);
                 }
+
+                // No follow-up continuation means the stream (and its chat) is over
+                let Some((next, timeout_ms)) = next_continuation(&json) else {
+                    eprintln!("No next continuation found for {}. Chat has ended.", video_id_clean);
+                    break "ended";
+                };
+                continuation = next;
+                timeout_ms.map_or(YOUTUBE_DEFAULT_POLL, |ms| Duration::from_millis(ms.clamp(YOUTUBE_MIN_POLL_MS, YOUTUBE_MAX_POLL_MS)))
             }
-            Err(e) => {
-                eprintln!("Chat request failed: {}", e);
-                tokio::time::sleep(Duration::from_secs(5)).await;
+            Err((e, retry_after)) => {
+                errors += 1;
+                // Rate limited: wait as long as we're told, and at least as long as our own backoff
+                let delay = youtube_backoff_delay(errors).max(retry_after.unwrap_or_default());
+                eprintln!("{}, retrying in {:?}", e, delay);
+                delay
             }
+        };
+
+        if errors >= YOUTUBE_MAX_CONSECUTIVE_ERRORS {
+            eprintln!("Giving up on YouTube chat for {} after {} failed requests", video_id_clean, errors);
+            break "error";
         }
-        
+
         // Cancellable Sleep
         tokio::select! {
              biased;
              _ = rx.recv() => {
                   eprintln!("YouTube handler received shutdown during sleep.");
-                  break;
+                  return;
              }
-             _ = tokio::time::sleep(Duration::from_secs(1)) => {}
+             _ = tokio::time::sleep(delay) => {}
+        }
+    };
+
+    emit_chat_ended(app, video_id_clean, reason);
+}
+
+// Plays back the chat of a finished stream against the video's timeline. Returns why playback
+// stopped, or None when the poller was shut down.
+async fn replay_live_chat(
+    app: &AppHandle,
+    bootstrap: &YouTubeBootstrap,
+    source: &ChatSource,
+    initial_continuation: &str,
+    options: ReplayOptions,
+    rx: &mut tokio::sync::broadcast::Receiver<()>,
+    commands_rx: &mut tokio::sync::mpsc::UnboundedReceiver<ReplayCommand>,
+) -> Option<&'static str> {
+    let client = Client::new();
+    let replay_url = format!("https://www.youtube.com/youtubei/v1/live_chat/get_live_chat_replay?key={}", bootstrap.api_key);
+
+    let mut clock = ReplayClock::new(options.start_offset_ms, options.speed.unwrap_or(1.0));
+    // Chat actions waiting for the clock to reach their video offset
+    let mut queue: VecDeque<(u64, Value)> = VecDeque::new();
+    let mut continuation = Some(initial_continuation.to_string());
+    // Set when the next request must jump to a new position instead of carrying on
+    let mut seek_to = Some(options.start_offset_ms);
+    let mut authors: HashMap<String, String> = HashMap::new();
+    let mut errors: u32 = 0;
+
+    emit_replay_state(app, source, &clock);
+
+    loop {
+        // None while paused: only a command or shutdown can move us on
+        let wait = if queue.is_empty() {
+            let Some(token) = continuation.clone() else {
+                return Some("ended");
+            };
+            let mut body = serde_json::json!({
+                "context": bootstrap.context(),
+                "continuation": token
+            });
+            if let Some(offset_ms) = seek_to {
+                body["currentPlayerState"] = serde_json::json!({ "playerOffsetMs": offset_ms.to_string() });
+            }
+
+            match innertube_post(&client, &replay_url, &body).await {
+                Ok(json) => {
+                    errors = 0;
+                    // A seek answers with the batch around the target; drop what comes before it
+                    let min_offset = seek_to.take().unwrap_or(0);
+                    queue.extend(replay_actions(&json).filter(|(offset, _)| *offset >= min_offset));
+                    // The last batch only offers a playerSeekContinuationData, which next_continuation ignores
+                    continuation = next_continuation(&json).map(|(next, _)| next);
+                    Some(if queue.is_empty() { REPLAY_MIN_REFETCH } else { Duration::ZERO })
+                }
+                Err((e, retry_after)) => {
+                    errors += 1;
+                    if errors >= YOUTUBE_MAX_CONSECUTIVE_ERRORS {
+                        eprintln!("Giving up on YouTube chat replay for {} after {} failed requests", source.channel, errors);
+                        return Some("error");
+                    }
+                    let delay = youtube_backoff_delay(errors).max(retry_after.unwrap_or_default());
+                    eprintln!("{}, retrying in {:?}", e, delay);
+                    Some(delay)
+                }
+            }
+        } else {
+            let position = clock.position();
+            while queue.front().is_some_and(|(offset, _)| *offset <= position) {
+                let (offset, action) = queue.pop_front().unwrap();
+                handle_chat_action(app, &action, source, &mut authors, 0, Some(offset));
+            }
+            match queue.front() {
+                Some((offset, _)) => clock.until(*offset),
+                None => Some(REPLAY_MIN_REFETCH),
+            }
+        };
+
+        tokio::select! {
+            biased;
+            _ = rx.recv() => {
+                eprintln!("YouTube replay received shutdown.");
+                return None;
+            }
+            Some(command) = commands_rx.recv() => {
+                eprintln!("YouTube replay command for {}: {:?}", source.channel, command);
+                match command {
+                    ReplayCommand::Pause => clock.pause(),
+                    ReplayCommand::Resume => clock.resume(),
+                    ReplayCommand::SetSpeed { speed } => clock.set_speed(speed),
+                    ReplayCommand::Seek { offset_ms } => {
+                        clock.seek(offset_ms);
+                        queue.clear();
+                        continuation = Some(initial_continuation.to_string());
+                        seek_to = Some(offset_ms);
+                    }
+                }
+                emit_replay_state(app, source, &clock);
+            }
+            _ = tokio::time::sleep(wait.unwrap_or_default()), if wait.is_some() => {}
+        }
+    }
+}
+
+// (videoOffsetTimeMsec, inner action) pairs from a get_live_chat_replay response
+fn replay_actions(json: &Value) -> impl Iterator<Item = (u64, Value)> + '_ {
+    json.pointer("/continuationContents/liveChatContinuation/actions")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|action| action.get("replayChatItemAction"))
+        .flat_map(|replay| {
+            let offset: u64 = replay["videoOffsetTimeMsec"].as_str().and_then(|s| s.parse().ok()).unwrap_or(0);
+            replay["actions"].as_array().into_iter().flatten().map(move |action| (offset, action.clone()))
+        })
+}
+
+// Position in the video being replayed, advancing with wall time scaled by the playback speed
+struct ReplayClock {
+    base_ms: u64,
+    since: Instant,
+    speed: f64,
+    paused: bool,
+}
+
+impl ReplayClock {
+    fn new(start_ms: u64, speed: f64) -> Self {
+        Self { base_ms: start_ms, since: Instant::now(), speed: speed.clamp(REPLAY_MIN_SPEED, REPLAY_MAX_SPEED), paused: false }
+    }
+
+    fn position(&self) -> u64 {
+        if self.paused {
+            return self.base_ms;
+        }
+        self.base_ms + (self.since.elapsed().as_millis() as f64 * self.speed) as u64
+    }
+
+    // Real time until the video reaches `offset_ms`; None while paused
+    fn until(&self, offset_ms: u64) -> Option<Duration> {
+        if self.paused {
+            return None;
+        }
+        let remaining = offset_ms.saturating_sub(self.position());
+        Some(Duration::from_millis((remaining as f64 / self.speed) as u64))
+    }
+
+    fn pause(&mut self) {
+        self.base_ms = self.position();
+        self.paused = true;
+    }
+
+    fn resume(&mut self) {
+        self.since = Instant::now();
+        self.paused = false;
+    }
+
+    fn set_speed(&mut self, speed: f64) {
+        self.base_ms = self.position();
+        self.since = Instant::now();
+        self.speed = speed.clamp(REPLAY_MIN_SPEED, REPLAY_MAX_SPEED);
+    }
+
+    fn seek(&mut self, offset_ms: u64) {
+        self.base_ms = offset_ms;
+        self.since = Instant::now();
+    }
+}
+
+fn emit_replay_state(app: &AppHandle, source: &ChatSource, clock: &ReplayClock) {
+    app.emit("youtube-replay-state", serde_json::json!({
+        "video_id": source.channel,
+        "position_ms": clock.position(),
+        "speed": clock.speed,
+        "paused": clock.paused,
+    })).unwrap_or(());
+}
+
+fn emit_chat_ended(app: &AppHandle, video_id: &str, reason: &str) {
+    app.emit("youtube-ended", serde_json::json!({ "video_id": video_id, "reason": reason })).unwrap_or(());
+}
+
+// Sends a replay control to the poller for `video_id`
+#[tauri::command]
+pub fn control_youtube_replay(app: AppHandle, video_id: String, command: ReplayCommand) -> Result<(), HeychatError> {
+    let state = app.state::<YouTubeState>();
+    let pollers = state.pollers.read().unwrap();
+    let poller = pollers.get(video_id.trim())
+        .ok_or_else(|| HeychatError::NotConnected(format!("Not connected to YouTube chat for {}", video_id)))?;
+    // Live pollers drop their command receiver, so sending only succeeds during a replay
+    poller.commands_tx.send(command)
+        .map_err(|_| HeychatError::NotConnected(format!("YouTube chat for {} is not a replay", video_id)))
+}
+
+// POSTs an innertube request. On failure, returns what went wrong and, for HTTP 429, the server's Retry-After.
+async fn innertube_post(client: &Client, url: &str, body: &Value) -> Result<Value, (String, Option<Duration>)> {
+    let resp = client.post(url).json(body).send().await.map_err(|e| (format!("Chat request failed: {}", e), None))?;
+    let status = resp.status();
+    if !status.is_success() {
+        let retry_after = (status == reqwest::StatusCode::TOO_MANY_REQUESTS)
+            .then(|| resp.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.parse::<u64>().ok())
+            .flatten()
+            .map(Duration::from_secs);
+        return Err((format!("YouTube chat request returned {}", status), retry_after));
+    }
+    resp.json::<Value>().await.map_err(|e| (format!("Failed to parse YouTube chat JSON: {}", e), None))
+}
+
+// Emits a single chat action. Live polling passes its connect time as `since_usec`, since YouTube
+// starts with a batch of history we don't want; replayed actions carry their position in the video.
+fn handle_chat_action(app: &AppHandle, action: &Value, source: &ChatSource, authors: &mut HashMap<String, String>, since_usec: u64, video_offset_ms: Option<u64>) {
+    if let Some((kind, renderer)) = action.pointer("/addChatItemAction/item").and_then(|v| v.as_object()).and_then(|o| o.iter().next()) {
+        let timestamp_usec: u64 = renderer["timestampUsec"].as_str().and_then(|s| s.parse().ok()).unwrap_or(0);
+        if timestamp_usec < since_usec {
+            return;
+        }
+
+        if let Some(mut chat_message) = parse_chat_item(kind, renderer, source) {
+            chat_message.video_offset_ms = video_offset_ms;
+            if let Some(channel_id) = &chat_message.user_id {
+                authors.insert(channel_id.clone(), chat_message.username.clone());
+            }
+            app.emit("chat-message", chat_message).unwrap_or(());
+        }
+    } else if let Some(moderation) = parse_moderation_action(action, authors) {
+        eprintln!("YouTube moderation in {}: {:?}", source.channel, moderation);
+        app.emit("chat-moderation", ModerationEvent::now(source.clone(), moderation)).unwrap_or(());
+    }
+}
+
+// The next continuation token and how long the server wants us to wait before using it
+fn next_continuation(json: &Value) -> Option<(String, Option<u64>)> {
+    let data = json.pointer("/continuationContents/liveChatContinuation/continuations/0")?;
+    let data = data.get("invalidationContinuationData")
+        .or_else(|| data.get("timedContinuationData"))
+        .or_else(|| data.get("liveChatReplayContinuationData"))
+        .or_else(|| data.get("reloadContinuationData"))?;
+    let continuation = data["continuation"].as_str()?.to_string();
+    Some((continuation, data["timeoutMs"].as_u64()))
+}
+
+fn youtube_backoff_delay(attempt: u32) -> Duration {
+    let exp = YOUTUBE_BACKOFF_BASE_MS.saturating_mul(1 << attempt.saturating_sub(1).min(16)).min(YOUTUBE_BACKOFF_MAX_MS);
+    let jitter = thread_rng().gen_range(0..=exp / 2);
+    Duration::from_millis(exp + jitter)
+}
+
+// Turns one addChatItemAction item into a ChatMessage. `kind` is the renderer name,
+// e.g. "liveChatTextMessageRenderer"; placeholders and engagement banners return None.
+fn parse_chat_item(kind: &str, renderer: &Value, source: &ChatSource) -> Option<ChatMessage> {
+    // Gift purchases keep the author inside a header renderer
+    let author = match kind {
+        "liveChatSponsorshipsGiftPurchaseAnnouncementRenderer" => renderer.pointer("/header/liveChatSponsorshipsHeaderRenderer")?,
+        _ => renderer,
+    };
+    let received_at = chrono::Utc::now();
+    let username = author.pointer("/authorName/simpleText").and_then(|v| v.as_str()).unwrap_or("Unknown").to_string();
+    let (message, emotes) = parse_message_runs(renderer.pointer("/message/runs"));
+
+    let (msg_type, system_message) = match kind {
+        "liveChatTextMessageRenderer" => (MessageType::Chat, None),
+        "liveChatPaidMessageRenderer" => {
+            let amount_text = text_of(&renderer["purchaseAmountText"]).unwrap_or_default();
+            let (amount, currency) = split_purchase_amount(&amount_text);
+            let msg_type = MessageType::SuperChat {
+                amount,
+                currency,
+                header_color: argb_to_hex(&renderer["headerBackgroundColor"]),
+                body_color: argb_to_hex(&renderer["bodyBackgroundColor"]),
+                text_color: argb_to_hex(&renderer["bodyTextColor"]),
+            };
+            (msg_type, Some(format!("Sent a {} Super Chat!", amount_text)))
+        }
+        "liveChatPaidStickerRenderer" => {
+            let amount_text = text_of(&renderer["purchaseAmountText"]).unwrap_or_default();
+            let (amount, currency) = split_purchase_amount(&amount_text);
+            // Thumbnails are listed smallest first and are protocol-relative ("//lh3...")
+            let sticker_url = renderer.pointer("/sticker/thumbnails").and_then(|v| v.as_array())
+                .and_then(|t| t.last())
+                .and_then(|t| t["url"].as_str())
+                .map(|url| if url.starts_with("//") { format!("https:{}", url) } else { url.to_string() });
+            let msg_type = MessageType::SuperSticker {
+                amount,
+                currency,
+                sticker_url,
+                sticker_label: renderer.pointer("/sticker/accessibility/accessibilityData/label").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                color: argb_to_hex(&renderer["backgroundColor"]),
+            };
+            (msg_type, Some(format!("Sent a {} Super Sticker!", amount_text)))
+        }
+        "liveChatMembershipItemRenderer" => {
+            // New members: headerSubtext is "Welcome to <level>!" in three runs.
+            // Milestones: headerPrimaryText is "Member for N months" and headerSubtext is the level.
+            let primary = text_of(&renderer["headerPrimaryText"]);
+            let subtext = text_of(&renderer["headerSubtext"]);
+            let level = match primary {
+                Some(_) => subtext.clone(),
+                None => renderer.pointer("/headerSubtext/runs").and_then(|v| v.as_array())
+                    .filter(|runs| runs.len() == 3)
+                    .and_then(|runs| runs[1]["text"].as_str())
+                    .map(|s| s.to_string()),
+            };
+            let months = primary.as_deref().and_then(first_number);
+            (MessageType::Membership { level, months }, primary.or(subtext))
+        }
+        "liveChatSponsorshipsGiftPurchaseAnnouncementRenderer" => {
+            // "Gifted 5 <channel> memberships"
+            let primary = text_of(&author["primaryText"]);
+            let count = primary.as_deref().and_then(first_number).unwrap_or(1);
+            (MessageType::MembershipGift { count }, primary)
+        }
+        _ => return None,
+    };
+
+    let (badges, badge_images) = parse_author_badges(author);
+    let is_mod = badges.iter().any(|b| b == "moderator");
+    let is_member = badges.iter().any(|b| b == "member");
+
+    let mut color = None;
+    if badges.iter().any(|b| b == "owner") {
+        color = Some("#ffd600".to_string()); // YouTube Owner Gold
+    } else if is_mod {
+        color = Some("#5e84f1".to_string()); // YouTube Mod Blue
+    } else if is_member {
+        color = Some("#0f9d58".to_string()); // YouTube Member Green
+    }
+
+    Some(ChatMessage {
+        id: renderer["id"].as_str().unwrap_or("").to_string(),
+        platform: Platform::YouTube,
+        source: source.clone(),
+        username,
+        login: None,
+        message,
+        color,
+        badges,
+        badge_images,
+        is_mod,
+        is_vip: false,
+        is_member,
+        timestamp: renderer["timestampUsec"].as_str()
+            .and_then(|usec| usec.parse().ok())
+            .and_then(chrono::DateTime::from_timestamp_micros)
+            .unwrap_or(received_at),
+        received_at,
+        emotes,
+        msg_type,
+        system_message,
+        video_offset_ms: None,
+        // Gift purchases only carry the channel id on the outer renderer
+        user_id: author["authorExternalChannelId"].as_str()
+            .or(renderer["authorExternalChannelId"].as_str())
+            .map(|s| s.to_string()),
+        avatar_url: author.pointer("/authorPhoto/thumbnails").and_then(|v| v.as_array())
+            .and_then(|t| t.last())
+            .and_then(|t| t["url"].as_str())
+            .map(|s| s.to_string()),
+    })
+}
+
+// Concatenates text and emoji runs. Emoji become their shortcut (e.g. :cat:) in the text,
+// with an Emote range pointing at the image.
+fn parse_message_runs(runs: Option<&Value>) -> (String, Vec<Emote>) {
+    let mut message_text = String::new();
+    let mut emotes = Vec::new();
+
+    if let Some(runs) = runs.and_then(|v| v.as_array()) {
+        for run in runs {
+            if let Some(text) = run["text"].as_str() {
+                message_text.push_str(text);
+            } else if let Some(emoji_node) = run.pointer("/emoji") {
+                // Handle Emoji
+                let mut emoji_text = "".to_string();
+                
+                // 1. Try to find a shortcut (e.g. :cat:) to use as text representation
+                if let Some(shortcuts) = emoji_node.pointer("/shortcuts").and_then(|v| v.as_array()) {
+                    if let Some(first) = shortcuts.first().and_then(|v| v.as_str()) {
+                        emoji_text = first.to_string();
+                    }
+                }
+                
+                // Fallback to emojiId if no shortcut
+                if emoji_text.is_empty() {
+                    if let Some(id) = emoji_node["emojiId"].as_str() {
+                        emoji_text = id.to_string();
+                    }
+                }
+
+                // 2. Get the Image URL
+                let mut image_url = "".to_string();
+                if let Some(thumbnails) = emoji_node.pointer("/image/thumbnails").and_then(|v| v.as_array()) {
+                     // Usually the last one is biggest? Or first? checking 0 is usually fine for chat
+                     if let Some(url) = thumbnails.first().and_then(|t| t["url"].as_str()) {
+                         image_url = url.to_string();
+                     }
+                }
+
+                if !emoji_text.is_empty() {
+                    // Emote ranges are in chars, not bytes (see models::Emote)
+                    let start_index = message_text.chars().count();
+                    
+                    message_text.push_str(&emoji_text);
+                    
+                    if !image_url.is_empty() {
+                        emotes.push(Emote {
+                            id: image_url,
+                            code: emoji_text.clone(),
+                            start: start_index,
+                            end: start_index + emoji_text.chars().count(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    (message_text, emotes)
+}
+
+// Badge icon types already reported, so each unknown one is logged once per run
+static UNKNOWN_BADGE_ICONS: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Default::default);
+
+// True the first time `icon` turns up unrecognised
+fn first_unknown_badge_icon(icon: &str) -> bool {
+    UNKNOWN_BADGE_ICONS.lock().unwrap().insert(icon.to_string())
+}
+
+// Badge names ("owner", "moderator", "verified", "member") and the images of custom badges.
+// Built-in badges are identified by icon type, since tooltips are localised; membership
+// badges have no icon, just the channel's own thumbnail.
+fn parse_author_badges(author: &Value) -> (Vec<String>, HashMap<String, String>) {
+    let mut badges = Vec::new();
+    let mut images = HashMap::new();
+
+    for badge in author["authorBadges"].as_array().into_iter().flatten() {
+        let badge = &badge["liveChatAuthorBadgeRenderer"];
+        let name = match badge.pointer("/icon/iconType").and_then(|v| v.as_str()) {
+            Some("OWNER") => "owner",
+            Some("MODERATOR") => "moderator",
+            Some("VERIFIED") | Some("VERIFIED_ARTIST") => "verified",
+            Some(other) => {
+                if first_unknown_badge_icon(other) {
+                    eprintln!("Unknown YouTube badge icon: {}", other);
+                }
+                continue;
+            }
+            None if badge.get("customThumbnail").is_some() => "member",
+            None => continue,
+        };
+        if let Some(url) = badge.pointer("/customThumbnail/thumbnails").and_then(|v| v.as_array())
+            .and_then(|t| t.last())
+            .and_then(|t| t["url"].as_str())
+        {
+            images.insert(name.to_string(), url.to_string());
         }
+        badges.push(name.to_string());
+    }
+
+    (badges, images)
+}
+
+// Plain text of a { simpleText } or { runs: [{ text }] } node
+fn text_of(node: &Value) -> Option<String> {
+    if let Some(text) = node["simpleText"].as_str() {
+        return Some(text.to_string());
+    }
+    let runs = node["runs"].as_array()?;
+    Some(runs.iter().filter_map(|run| run["text"].as_str()).collect())
+}
+
+fn first_number(text: &str) -> Option<u64> {
+    let digits: String = text.chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit() || *c == ',')
+        .filter(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+// "$5.00" -> ("5.00", Some("$")), "PHP 100.00" -> ("100.00", Some("PHP")), "10,00 €" -> ("10,00", Some("€"))
+fn split_purchase_amount(text: &str) -> (String, Option<String>) {
+    let (Some(first), Some(last)) = (text.find(|c: char| c.is_ascii_digit()), text.rfind(|c: char| c.is_ascii_digit())) else {
+        return (text.to_string(), None);
+    };
+    let currency = format!("{}{}", &text[..first], &text[last + 1..]).trim().to_string();
+    let currency = if currency.is_empty() { None } else { Some(currency) };
+    (text[first..=last].to_string(), currency)
+}
+
+// YouTube colours are ARGB packed into an integer; the frontend wants "#RRGGBB"
+fn argb_to_hex(value: &Value) -> Option<String> {
+    let argb = value.as_u64()?;
+    Some(format!("#{:06X}", argb & 0xFF_FFFF))
+}
+
+// Deletions arrive either as removals (item gone) or "mark as deleted" (item kept with a
+// "[message retracted]" placeholder). Both mean the message should disappear from the feed.
+fn parse_moderation_action(action: &Value, authors: &HashMap<String, String>) -> Option<ModerationAction> {
+    let message_id = action.pointer("/removeChatItemAction/targetItemId")
+        .or_else(|| action.pointer("/markChatItemAsDeletedAction/targetItemId"))
+        .and_then(|v| v.as_str());
+    if let Some(message_id) = message_id {
+        return Some(ModerationAction::MessageDeleted { message_id: message_id.to_string() });
     }
+
+    // Issued when a moderator hides, times out or bans an author; YouTube doesn't say which
+    let channel_id = action.pointer("/removeChatItemByAuthorAction/externalChannelId")
+        .or_else(|| action.pointer("/markChatItemsByAuthorAsDeletedAction/externalChannelId"))
+        .and_then(|v| v.as_str())?;
+    Some(ModerationAction::UserBanned {
+        username: authors.get(channel_id).cloned().unwrap_or_else(|| channel_id.to_string()),
+        user_id: Some(channel_id.to_string()),
+    })
+}
+
+// Channel name of the video owner, as embedded in the watch page's player microformat
+fn extract_owner_channel_name(html: &str) -> Option<String> {
+    let owner_regex = Regex::new(r#""ownerChannelName":("(?:[^"\\]|\\.)*")"#).unwrap();
+    let caps = owner_regex.captures(html)?;
+    // The capture is a JSON string literal; decode it so escapes like \u0026 come out right
+    serde_json::from_str::<String>(caps.get(1)?.as_str()).ok()
 }
 
-pub async fn leave_youtube_channel(app: AppHandle) {
-    eprintln!("Leaving YouTube channel...");
+pub async fn leave_youtube_channel(app: AppHandle, video_id: String) {
+    let video_id = video_id.trim();
+    eprintln!("Leaving YouTube chat for video: {}", video_id);
     let state = app.state::<YouTubeState>();
-    let mut guard = state.shutdown_tx.write().unwrap();
-    if let Some(tx) = guard.take() {
-        let _ = tx.send(());
+    let poller = state.pollers.write().unwrap().remove(video_id);
+    if let Some(poller) = poller {
+        let _ = poller.shutdown_tx.send(());
     }
+    state.send_sessions.write().unwrap().remove(video_id);
 }
 
 #[derive(serde::Deserialize, Debug)]
@@ -397,28 +852,131 @@ struct LiveStreamingDetails {
     is_chat_moderator: Option<bool>,
 }
 
-#[tauri::command]
-pub async fn send_youtube_message(video_id: String, message: String, token: String) -> Result<(), String> {
-    eprintln!("DEBUG: send_youtube_message called.");
-    eprintln!("DEBUG: Input Video ID: {}", video_id);
-    eprintln!("DEBUG: Token starts with: {:.10}...", token);
+// How messages are sent to YouTube chat. The Data API is the official route but every message
+// costs quota (10k units a day by default); innertube is what the web chat uses and has no daily cap,
+// but it only accepts a signed-in youtube.com session (cookies), not the OAuth token.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum YouTubeSendMethod {
+    #[default]
+    DataApi,
+    Innertube,
+}
+
+// Where messages for a video go, as resolved for one send method
+#[derive(Debug, Clone)]
+pub enum YouTubeSendTarget {
+    DataApi { live_chat_id: String },
+    Innertube { bootstrap: YouTubeBootstrap, params: String },
+}
+
+impl YouTubeSendTarget {
+    fn method(&self) -> YouTubeSendMethod {
+        match self {
+            YouTubeSendTarget::DataApi { .. } => YouTubeSendMethod::DataApi,
+            YouTubeSendTarget::Innertube { .. } => YouTubeSendMethod::Innertube,
+        }
+    }
+
+    // Identifies the chat being posted to, to tell whether a fresh lookup found a different one
+    fn id(&self) -> &str {
+        match self {
+            YouTubeSendTarget::DataApi { live_chat_id } => live_chat_id,
+            YouTubeSendTarget::Innertube { params, .. } => params,
+        }
+    }
+}
 
+// What send_youtube_message resolved for a video. Reused for every message until a send fails,
+// so a long stream doesn't spend Data API quota re-probing the same chat.
+#[derive(Debug, Clone)]
+pub struct YouTubeSendSession {
+    pub credential: String, // OAuth token or cookies the target was resolved with
+    pub target: YouTubeSendTarget,
+}
+
+#[tauri::command]
+pub async fn send_youtube_message(
+    app: AppHandle,
+    video_id: String,
+    message: String,
+    token: String,
+    method: Option<YouTubeSendMethod>,
+    cookies: Option<String>, // youtube.com Cookie header, needed for innertube
+) -> Result<(), HeychatError> {
+    let video_id = video_id.trim().to_string();
+    let method = method.unwrap_or_default();
+    let credential = match method {
+        YouTubeSendMethod::DataApi => token,
+        YouTubeSendMethod::Innertube => cookies.map(|c| c.trim().to_string()).filter(|c| !c.is_empty())
+            .ok_or_else(|| HeychatError::Unauthorized("Sending through the web chat needs your youtube.com cookies".to_string()))?,
+    };
     let client = Client::builder()
         .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
         .build()
         .unwrap_or_default();
 
+    let state = app.state::<YouTubeState>();
+    let cached = state.send_sessions.read().unwrap().get(&video_id)
+        .filter(|session| session.credential == credential && session.target.method() == method)
+        .map(|session| session.target.clone());
+
+    let was_cached = cached.is_some();
+    let target = match cached {
+        Some(target) => target,
+        None => resolve_send_target(&app, &client, &video_id, &credential, method).await?,
+    };
+    let Err(err) = post_to_target(&client, &credential, &target, &message).await else {
+        return Ok(());
+    };
+
+    // The chat may have ended or the token lost access since we resolved it. A fresh lookup
+    // reports why; if it finds a different chat, try that once. Quota errors won't improve.
+    state.send_sessions.write().unwrap().remove(&video_id);
+    if !was_cached || matches!(err, HeychatError::RateLimited { .. }) {
+        return Err(err);
+    }
+    eprintln!("YouTube send failed with cached chat ({}), re-checking", err);
+    let refreshed = resolve_send_target(&app, &client, &video_id, &credential, method).await?;
+    if refreshed.id() == target.id() {
+        return Err(err);
+    }
+    post_to_target(&client, &credential, &refreshed, &message).await
+}
+
+// Looks up where to send for `method` and caches it for later messages. `credential` is the
+// OAuth token for the Data API and the Cookie header for innertube.
+async fn resolve_send_target(app: &AppHandle, client: &Client, video_id: &str, credential: &str, method: YouTubeSendMethod) -> Result<YouTubeSendTarget, HeychatError> {
+    let target = match method {
+        YouTubeSendMethod::DataApi => YouTubeSendTarget::DataApi { live_chat_id: resolve_live_chat_id(client, video_id, credential).await? },
+        YouTubeSendMethod::Innertube => resolve_innertube_target(client, video_id, credential).await?,
+    };
+    app.state::<YouTubeState>().send_sessions.write().unwrap().insert(video_id.to_string(), YouTubeSendSession {
+        credential: credential.to_string(),
+        target: target.clone(),
+    });
+    Ok(target)
+}
+
+async fn post_to_target(client: &Client, credential: &str, target: &YouTubeSendTarget, message: &str) -> Result<(), HeychatError> {
+    match target {
+        YouTubeSendTarget::DataApi { live_chat_id } => post_live_chat_message(client, credential, live_chat_id, message).await,
+        YouTubeSendTarget::Innertube { bootstrap, params } => post_innertube_message(client, credential, bootstrap, params, message).await,
+    }
+}
+
+// Verifies the token and looks up the video's live chat through the Data API
+async fn resolve_live_chat_id(client: &Client, video_id: &str, token: &str) -> Result<String, HeychatError> {
     // 0. Verify Token Scopes
     let scope_url = format!("https://www.googleapis.com/oauth2/v3/tokeninfo?access_token={}", token);
-    eprintln!("DEBUG: Checking Token Scopes: {}", scope_url);
-    let scope_resp = client.get(&scope_url).send().await.map_err(|e| format!("Scope check failed: {}", e))?;
+    let scope_resp = client.get(&scope_url).send().await?;
     
     if scope_resp.status().is_success() {
         let scope_json: Value = scope_resp.json().await.unwrap_or(serde_json::json!({}));
         if let Some(scope) = scope_json.get("scope").and_then(|s| s.as_str()) {
             eprintln!("DEBUG: Token Scopes: {}", scope);
             if !scope.contains("youtube.force-ssl") && !scope.contains("youtube") { // "youtube" is the full scope, force-ssl is the other one
-                return Err("Missing 'youtube.force-ssl' scope. Please log out and log in again.".to_string());
+                return Err(HeychatError::MissingScope("youtube.force-ssl".to_string()));
             }
         } else {
              eprintln!("DEBUG: Could not parse scopes from response: {:?}", scope_json);
@@ -429,105 +987,133 @@ pub async fn send_youtube_message(video_id: String, message: String, token: Stri
 
     // 0. Verify YouTube Data API Access AND Channel Existence
     let probe_url = "https://www.googleapis.com/youtube/v3/channels?part=id&mine=true";
-    eprintln!("DEBUG: Probing YouTube Data API: {}", probe_url);
     let probe_resp = client.get(probe_url)
         .header("Authorization", format!("Bearer {}", token))
         .send()
-        .await
-        .map_err(|e| format!("Probe failed: {}", e))?;
+        .await?;
         
     if !probe_resp.status().is_success() {
-        let status = probe_resp.status();
-        let body = probe_resp.text().await.unwrap_or_default();
-        eprintln!("DEBUG: YouTube Data API Probe Failed. Status: {}, Body: {}", status, body);
-        if status.as_u16() == 403 || status.as_u16() == 404 {
-            return Err("YouTube Data API v3 is likely NOT enabled in Google Cloud Console. Enable it here: https://console.cloud.google.com/apis/library/youtube.googleapis.com".to_string());
-        }
-        return Err(format!("YouTube Data API Probe failed: [{}] {}", status, body));
+        let err = HeychatError::from_response(probe_resp).await;
+        eprintln!("DEBUG: YouTube Data API Probe Failed: {}", err);
+        return Err(err);
     }
 
     // Check if user actually has a channel
     let probe_json: Value = probe_resp.json().await.unwrap_or(serde_json::json!({}));
-    if let Some(items) = probe_json.get("items").and_then(|i| i.as_array()) {
-        if items.is_empty() {
-            eprintln!("DEBUG: Probe returned 0 channels. User likely has no YouTube Channel created.");
-            return Err("Your Google Account does not have a YouTube Channel. You must create a channel to use Live Chat.".to_string());
-        } else {
-             eprintln!("DEBUG: Probe found {} channel(s). ID: {:?}", items.len(), items[0].pointer("/id"));
-        }
-    } else {
-        eprintln!("DEBUG: Probe response format unexpected: {:?}", probe_json);
+    if probe_json.get("items").and_then(|i| i.as_array()).is_some_and(|items| items.is_empty()) {
+        eprintln!("DEBUG: Probe returned 0 channels. User likely has no YouTube Channel created.");
+        return Err(HeychatError::NotFound("Your Google Account does not have a YouTube Channel. You must create a channel to use Live Chat.".to_string()));
     }
 
     // 1. Get Live Chat ID
     let list_url = format!("https://www.googleapis.com/youtube/v3/videos?part=liveStreamingDetails&id={}", video_id);
-    eprintln!("DEBUG: Fetching video details from: {}", list_url);
-    
     let resp = client.get(&list_url)
         .header("Authorization", format!("Bearer {}", token))
         .send()
-        .await
-        .map_err(|e| format!("Network error fetching video: {}", e))?;
+        .await?;
 
     if !resp.status().is_success() {
-         let status = resp.status();
-         let txt = resp.text().await.unwrap_or_default();
-         eprintln!("DEBUG: Failed to get video details. Status: {}", status);
-         return Err(format!("Failed to get video details: {}", txt));
+         eprintln!("DEBUG: Failed to get video details. Status: {}", resp.status());
+         return Err(HeychatError::from_response(resp).await);
     }
 
-    let list_data: VideoListResponse = resp.json().await.map_err(|e| format!("JSON parse error (video): {}", e))?;
-    
-    if list_data.items.is_empty() {
-        eprintln!("DEBUG: Video details response has 0 items.");
-        return Err("No video found with that ID".to_string());
+    let list_data: VideoListResponse = resp.json().await?;
+    let details = list_data.items.first()
+        .ok_or_else(|| HeychatError::NotFound("No video found with that ID".to_string()))?
+        .live_streaming_details.as_ref();
+
+    if let Some(end_time) = details.and_then(|d| d.actual_end_time.as_ref()) {
+        eprintln!("DEBUG: Stream has ended at {}", end_time);
+        return Err(HeychatError::NotConnected("Cannot send message: The live stream has ended.".to_string()));
     }
 
-    let item = list_data.items.first().unwrap();
-    let details = item.live_streaming_details.as_ref();
-    
-    eprintln!("DEBUG: Live Streaming Details: {:?}", details);
+    let chat_id = details
+        .and_then(|d| d.active_live_chat_id.clone())
+        .ok_or_else(|| HeychatError::NotFound("No active live chat found. Is the stream live?".to_string()))?;
 
-    if let Some(d) = details {
-        if let Some(end_time) = &d.actual_end_time {
-            eprintln!("DEBUG: Stream has ended at {}", end_time);
-            return Err("Cannot send message: The live stream has ended.".to_string());
-        }
+    eprintln!("DEBUG: Found Live Chat ID for {}: {}", video_id, chat_id);
+    Ok(chat_id)
+}
+
+// Loads the live chat page as the signed-in user; its chat input carries the params for send_message
+async fn resolve_innertube_target(client: &Client, video_id: &str, cookies: &str) -> Result<YouTubeSendTarget, HeychatError> {
+    let url = format!("https://www.youtube.com/live_chat?is_popout=1&v={}", video_id);
+    let resp = client.get(&url)
+        .header("Cookie", cookies)
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        return Err(HeychatError::from_response(resp).await);
     }
+    innertube_target_from_page(&resp.text().await?)
+}
 
-    let chat_id = details
-        .and_then(|d| d.active_live_chat_id.as_ref())
-        .ok_or("No active live chat found. Is the stream live?")?;
+fn innertube_target_from_page(html: &str) -> Result<YouTubeSendTarget, HeychatError> {
+    let bootstrap = YouTubeBootstrap::parse(html).map_err(HeychatError::NotFound)?;
+    if bootstrap.is_replay {
+        return Err(HeychatError::NotConnected("Cannot send message: The live stream has ended.".to_string()));
+    }
+    // No chat input means YouTube doesn't consider us signed in, or chat is restricted (e.g. members only)
+    let params = bootstrap.send_message_params.clone()
+        .ok_or_else(|| HeychatError::Unauthorized("YouTube did not offer a chat box for this stream. Check that your cookies are current and you are allowed to chat.".to_string()))?;
+    Ok(YouTubeSendTarget::Innertube { bootstrap, params })
+}
 
-    eprintln!("DEBUG: Found Live Chat ID: {}", chat_id);
+// The Authorization header youtube.com expects alongside its session cookies:
+// "SAPISIDHASH <time>_<sha1 of '<time> <SAPISID> <origin>'>"
+fn sapisid_hash(cookies: &str, now_secs: u64) -> Option<String> {
+    let sapisid = cookies.split(';')
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == "SAPISID" || *name == "__Secure-3PAPISID")
+        .map(|(_, value)| value)?;
+    let digest = Sha1::digest(format!("{} {} {}", now_secs, sapisid, YOUTUBE_ORIGIN));
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    Some(format!("SAPISIDHASH {}_{}", now_secs, hex))
+}
 
-    // 1.5 Verify Chat ID access (GET list)
-    let encoded_chat_id = urlencoding::encode(chat_id);
-    let check_url = format!("https://www.googleapis.com/youtube/v3/liveChatMessages?liveChatId={}&part=snippet", encoded_chat_id);
-    eprintln!("DEBUG: GET Check URL: {}", check_url);
+async fn post_innertube_message(client: &Client, cookies: &str, bootstrap: &YouTubeBootstrap, params: &str, message: &str) -> Result<(), HeychatError> {
+    let auth = sapisid_hash(cookies, chrono::Utc::now().timestamp() as u64)
+        .ok_or_else(|| HeychatError::Unauthorized("Your youtube.com cookies have no SAPISID; copy them again while signed in".to_string()))?;
+    let url = format!("https://www.youtube.com/youtubei/v1/live_chat/send_message?key={}&prettyPrint=false", bootstrap.api_key);
+    let client_message_id: String = thread_rng().sample_iter(rand::distributions::Alphanumeric).take(26).map(char::from).collect();
+    let body = serde_json::json!({
+        "context": bootstrap.context(),
+        "params": params,
+        "clientMessageId": client_message_id,
+        "richMessage": { "textSegments": [{ "text": message }] },
+    });
 
-    let check_resp = client.get(&check_url)
-        .header("Authorization", format!("Bearer {}", token))
+    let resp = client.post(&url)
+        .header("Cookie", cookies)
+        .header("Authorization", auth)
+        .header("Origin", YOUTUBE_ORIGIN)
+        .header("X-Origin", YOUTUBE_ORIGIN)
+        .header("X-Goog-AuthUser", "0")
+        .json(&body)
         .send()
-        .await
-        .map_err(|e| e.to_string())?;
-    
-    if !check_resp.status().is_success() {
-        let s = check_resp.status();
-        let t = check_resp.text().await.unwrap_or_default();
-        eprintln!("DEBUG: GET check failed. Status: {}, Body: {}", s, t);
-        // We warn but proceed? Or fail? If GET fails, POST will likely fail.
-        // It's useful to know.
-    } else {
-        eprintln!("DEBUG: GET check passed (Live Chat is readable).");
+        .await?;
+    if !resp.status().is_success() {
+        let err = HeychatError::from_response(resp).await;
+        eprintln!("Failed to send YouTube message via innertube: {}", err);
+        return Err(err);
+    }
+
+    // Rejections (slow mode, blocked words, ...) come back as 200 with an error renderer
+    let json: Value = resp.json().await?;
+    if let Some(error) = json.get("errorMessage") {
+        let text = error.pointer("/liveChatTextActionsMessageRenderer/bodyText").and_then(text_of)
+            .unwrap_or_else(|| error.to_string());
+        return Err(HeychatError::PlatformError { status: 200, body: text });
     }
+    Ok(())
+}
 
-    // 2. Post Message
+async fn post_live_chat_message(client: &Client, token: &str, live_chat_id: &str, message: &str) -> Result<(), HeychatError> {
     let url = "https://www.googleapis.com/youtube/v3/liveChatMessages?part=snippet";
     
     let body = serde_json::json!({
         "snippet": {
-            "liveChatId": chat_id,
+            "liveChatId": live_chat_id,
             "type": "textMessageEvent",
             "textMessageDetails": {
                 "messageText": message
@@ -535,21 +1121,191 @@ pub async fn send_youtube_message(video_id: String, message: String, token: Stri
         }
     });
 
-    eprintln!("DEBUG: Posting message to {}", url);
     let post_resp = client.post(url)
         .header("Authorization", format!("Bearer {}", token))
         .json(&body)
         .send()
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
 
     if !post_resp.status().is_success() {
-        let status = post_resp.status();
-        let txt = post_resp.text().await.unwrap_or_default();
-        eprintln!("Failed to send YouTube message. Status: {}, Body: {}", status, txt);
-        return Err(format!("Failed to send message: [{}] {}", status, txt));
+        let err = HeychatError::from_response(post_resp).await;
+        eprintln!("Failed to send YouTube message: {}", err);
+        return Err(err);
     }
-
-    eprintln!("DEBUG: Message sent successfully!");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_amount_from_currency() {
+        assert_eq!(split_purchase_amount("$5.00"), ("5.00".to_string(), Some("$".to_string())));
+        assert_eq!(split_purchase_amount("CA$5.00"), ("5.00".to_string(), Some("CA$".to_string())));
+        assert_eq!(split_purchase_amount("¥1,000"), ("1,000".to_string(), Some("¥".to_string())));
+        assert_eq!(split_purchase_amount("€ 2,50"), ("2,50".to_string(), Some("€".to_string())));
+        assert_eq!(split_purchase_amount("10,00 €"), ("10,00".to_string(), Some("€".to_string())));
+        assert_eq!(split_purchase_amount("PHP 100.00"), ("100.00".to_string(), Some("PHP".to_string())));
+    }
+
+    #[test]
+    fn backoff_starts_at_base_and_caps() {
+        for _ in 0..20 {
+            let first = youtube_backoff_delay(1).as_millis() as u64;
+            assert!((YOUTUBE_BACKOFF_BASE_MS..=YOUTUBE_BACKOFF_BASE_MS * 3 / 2).contains(&first));
+            let second = youtube_backoff_delay(2).as_millis() as u64;
+            assert!((YOUTUBE_BACKOFF_BASE_MS * 2..=YOUTUBE_BACKOFF_BASE_MS * 3).contains(&second));
+            let capped = youtube_backoff_delay(40).as_millis() as u64;
+            assert!((YOUTUBE_BACKOFF_MAX_MS..=YOUTUBE_BACKOFF_MAX_MS * 3 / 2).contains(&capped));
+        }
+    }
+
+    #[test]
+    fn replay_clock_paces_offsets_by_speed() {
+        let clock = ReplayClock::new(10_000, 2.0);
+        let wait = clock.until(12_000).unwrap();
+        assert!(wait <= Duration::from_millis(1_000) && wait > Duration::from_millis(900));
+        assert_eq!(clock.until(9_000), Some(Duration::ZERO));
+
+        let mut clock = ReplayClock::new(10_000, 100.0);
+        assert_eq!(clock.speed, REPLAY_MAX_SPEED);
+        clock.pause();
+        let paused_at = clock.position();
+        assert_eq!(clock.until(paused_at + 1_000), None);
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(clock.position(), paused_at);
+        clock.resume();
+        assert!(clock.until(paused_at + 1_000).is_some());
+    }
+
+    #[test]
+    fn replay_clock_seeks_past_buffered_items() {
+        let mut clock = ReplayClock::new(0, 1.0);
+        let buffered = [5_000u64, 30_000, 45_000];
+        assert!(buffered.iter().all(|offset| clock.until(*offset) > Some(Duration::ZERO)));
+        clock.seek(60_000);
+        assert!(clock.position() >= 60_000);
+        assert!(buffered.iter().all(|offset| clock.until(*offset) == Some(Duration::ZERO)));
+        // Seeking back makes the same offsets wait again
+        clock.seek(1_000);
+        assert!(clock.position() < 5_000);
+        assert!(clock.until(5_000).unwrap() > Duration::from_millis(3_000));
+    }
+
+    #[test]
+    fn maps_badge_icons_and_member_thumbnails() {
+        let author: Value = serde_json::from_str(include_str!("../tests/fixtures/youtube/author_badges.json")).unwrap();
+        let (badges, images) = parse_author_badges(&author);
+        assert_eq!(badges, ["owner", "moderator", "verified", "member"]);
+        assert_eq!(images.len(), 1);
+        assert_eq!(images["member"], "https://yt3.ggpht.com/member=s32-c-k");
+    }
+
+    #[test]
+    fn reports_unknown_badge_icons_once() {
+        let author: Value = serde_json::from_str(include_str!("../tests/fixtures/youtube/author_badges.json")).unwrap();
+        parse_author_badges(&author);
+        parse_author_badges(&author);
+        assert!(!first_unknown_badge_icon("SPARKLE_BADGE"));
+        assert!(first_unknown_badge_icon("TEST_ONLY_BADGE"));
+        assert!(!first_unknown_badge_icon("TEST_ONLY_BADGE"));
+    }
+
+    fn moderation_fixture(name: &str) -> Value {
+        let fixtures: Value = serde_json::from_str(include_str!("../tests/fixtures/youtube/moderation_actions.json")).unwrap();
+        fixtures[name].clone()
+    }
+
+    #[test]
+    fn removed_items_become_deleted_messages() {
+        let authors = HashMap::new();
+        for (name, id) in [
+            ("removeChatItem", "ChwKGkNJdlV3cUxyNW9rREZlN0N3Z1FkaWY4Tk1R"),
+            ("markDeleted", "ChwKGkNPN2Q0NHJyNW9rREZZbkh3Z1FkUWNBRWd3"),
+        ] {
+            match parse_moderation_action(&moderation_fixture(name), &authors) {
+                Some(ModerationAction::MessageDeleted { message_id }) => assert_eq!(message_id, id),
+                other => panic!("{}: unexpected {:?}", name, other),
+            }
+        }
+        assert!(parse_moderation_action(&moderation_fixture("unrelated"), &authors).is_none());
+    }
+
+    #[test]
+    fn removed_authors_are_named_from_seen_messages() {
+        let authors = HashMap::from([("UCq1DXvBsE3EcFh8kBbEpHaQ".to_string(), "Some Viewer".to_string())]);
+        match parse_moderation_action(&moderation_fixture("removeByAuthor"), &authors) {
+            Some(ModerationAction::UserBanned { username, user_id }) => {
+                assert_eq!(username, "Some Viewer");
+                assert_eq!(user_id.as_deref(), Some("UCq1DXvBsE3EcFh8kBbEpHaQ"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unseen_removed_author_falls_back_to_channel_id() {
+        let authors = HashMap::from([("UCq1DXvBsE3EcFh8kBbEpHaQ".to_string(), "Some Viewer".to_string())]);
+        match parse_moderation_action(&moderation_fixture("removeByUnknownAuthor"), &authors) {
+            Some(ModerationAction::UserBanned { username, user_id }) => {
+                assert_eq!(username, "UC7mPJxfJtA8lQ9Cz8D1yqLw");
+                assert_eq!(user_id.as_deref(), Some("UC7mPJxfJtA8lQ9Cz8D1yqLw"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn logged_out_page_fails_to_resolve_a_send_target() {
+        let page = include_str!("../tests/fixtures/youtube/live_chat_popout_logged_out.html");
+        assert!(matches!(innertube_target_from_page(page), Err(HeychatError::Unauthorized(_))));
+    }
+
+    #[test]
+    fn signed_in_page_resolves_a_send_target() {
+        let page = include_str!("../tests/fixtures/youtube/live_chat_popout.html");
+        let target = innertube_target_from_page(page).unwrap();
+        assert_eq!(target.method(), YouTubeSendMethod::Innertube);
+        assert_eq!(target.id(), "Q2lrcUp3b1lWVU5UU2pNMFZHOXdaREV3Y1VwaVUweDBkbVJDWkdKd0VndHFSbDloUm5KTGVYWlZUUkFCR0FFJTNE");
+    }
+
+    #[test]
+    fn signs_innertube_requests_with_sapisid() {
+        let cookies = "PREF=f6=40000000; SAPISID=AbCdEfGhIjKlMnOp/QrStUvWxYz0123456; SID=abc";
+        assert_eq!(
+            sapisid_hash(cookies, 1_700_000_000).as_deref(),
+            Some("SAPISIDHASH 1700000000_0c06fc4839b57c5f6c0e45d8bd73429f7d2af32a")
+        );
+        assert_eq!(
+            sapisid_hash("__Secure-3PAPISID=AbCdEfGhIjKlMnOp/QrStUvWxYz0123456", 1_700_000_000),
+            sapisid_hash(cookies, 1_700_000_000)
+        );
+        assert_eq!(sapisid_hash("PREF=f6=40000000; SID=abc", 1_700_000_000), None);
+    }
+
+    #[test]
+    fn amount_without_digits_is_kept_as_is() {
+        assert_eq!(split_purchase_amount(""), (String::new(), None));
+        assert_eq!(split_purchase_amount("Free"), ("Free".to_string(), None));
+        assert_eq!(split_purchase_amount("5"), ("5".to_string(), None));
+    }
+
+    #[test]
+    fn converts_argb_to_rgb_hex() {
+        assert_eq!(argb_to_hex(&serde_json::json!(4280191205u64)), Some("#1E88E5".to_string()));
+        assert_eq!(argb_to_hex(&serde_json::json!(0x00_00_00_0Fu64)), Some("#00000F".to_string()));
+        assert_eq!(argb_to_hex(&serde_json::json!("#1E88E5")), None);
+        assert_eq!(argb_to_hex(&serde_json::json!(-1)), None);
+        assert_eq!(argb_to_hex(&Value::Null), None);
+    }
+
+    #[test]
+    fn reads_first_number_in_text() {
+        assert_eq!(first_number("Gifted 5 memberships"), Some(5));
+        assert_eq!(first_number("Sent 1,000 gifts"), Some(1000));
+        assert_eq!(first_number("Member for 12 months, level 3"), Some(12));
+        assert_eq!(first_number("Welcome!"), None);
+        assert_eq!(first_number(""), None);
+    }
+}
@@ -3,7 +3,7 @@ import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { getVersion } from "@tauri-apps/api/app";
 import { Eraser, Search as SearchIcon, Github, Heart, Shield, Bot, LogIn, Settings, Zap } from "lucide-react";
-import { ChatMessage } from "./types";
+import { ChatMessage, ModerationEvent, YouTubeChatMode, YouTubeReplayCommand, YouTubeReplayState, YouTubeSendMethod } from "./types";
 import { ChatList } from "./components/ChatList";
 import TitleBar from "./components/TitleBar";
 import { UpdateNotification } from "./components/UpdateNotification";
@@ -15,6 +15,7 @@ import "./App.css";
 
 import { ToastContainer, ToastMessage } from "./components/Toast";
 import { fetchThirdPartyEmotes, EmoteMap, EmoteData } from "./utils/emotes";
+import { errorMessage } from "./utils/errors";
 import { TwitchUserCard } from "./components/TwitchUserCard";
 import { StreamToolsModal } from "./components/StreamToolsModal";
 
@@ -32,6 +33,12 @@ function AppContent() {
   const [messages, setMessages] = useState<ChatMessage[]>([]);
   const [twitchChannel, setTwitchChannel] = useState("");
   const [youtubeVideoId, setYoutubeVideoId] = useState("");
+  const [youtubeChatMode, setYoutubeChatMode] = useState<YouTubeChatMode>("live");
+  const [youtubeSendMethod, setYoutubeSendMethod] = useState<YouTubeSendMethod>("data_api");
+  // youtube.com Cookie header; web chat sending signs in with the browser session, not OAuth.
+  // It is a full Google session, so it only lives in memory for this run.
+  const [youtubeCookies, setYoutubeCookies] = useState("");
+  const [youtubeReplay, setYoutubeReplay] = useState<YouTubeReplayState | null>(null);
   const [kickChannel, setKickChannel] = useState("");
   const [twitchConnected, setTwitchConnected] = useState(false);
   const [youtubeConnected, setYoutubeConnected] = useState(false);
@@ -69,7 +76,9 @@ function AppContent() {
   const [kickUser, setKickUser] = useState("");
   const [kickToken, setKickToken] = useState("");
 
-  const [selectedUser, setSelectedUser] = useState<string | null>(null);
+  // Message whose author was clicked; moderation targets that message's channel and user
+  const [selectedMessage, setSelectedMessage] = useState<ChatMessage | null>(null);
+  const selectedUser = selectedMessage?.username ?? null;
   const [isStreamToolsOpen, setIsStreamToolsOpen] = useState(false);
   const [isCurrentUserMod, setIsCurrentUserMod] = useState(false);
   const [broadcasterId, setBroadcasterId] = useState<string | null>(null);
@@ -102,6 +111,12 @@ function AppContent() {
 
     if (savedTwitch) setTwitchChannel(savedTwitch);
     if (savedYoutube) setYoutubeVideoId(savedYoutube);
+    const savedYoutubeChatMode = localStorage.getItem("heychat_youtube_chat_mode");
+    if (savedYoutubeChatMode === "top" || savedYoutubeChatMode === "live") setYoutubeChatMode(savedYoutubeChatMode);
+    const savedYoutubeSendMethod = localStorage.getItem("heychat_youtube_send_method");
+    if (savedYoutubeSendMethod === "data_api" || savedYoutubeSendMethod === "innertube") setYoutubeSendMethod(savedYoutubeSendMethod);
+    // Older builds persisted the cookies; don't leave the session lying around
+    localStorage.removeItem("heychat_youtube_cookies");
     if (savedKick) setKickChannel(savedKick);
 
     if (savedSidebar !== null) setIsSidebarOpen(savedSidebar === "true");
@@ -118,6 +133,8 @@ function AppContent() {
   useEffect(() => {
     localStorage.setItem("heychat_twitch_channel", twitchChannel);
     localStorage.setItem("heychat_youtube_id", youtubeVideoId);
+    localStorage.setItem("heychat_youtube_chat_mode", youtubeChatMode);
+    localStorage.setItem("heychat_youtube_send_method", youtubeSendMethod);
 
     localStorage.setItem("heychat_sidebar_open", String(isSidebarOpen));
     localStorage.setItem("heychat_favorites", favoritesInput);
@@ -128,7 +145,7 @@ function AppContent() {
     localStorage.setItem("heychat_kick_username", kickUser);
     localStorage.setItem("heychat_kick_token", kickToken);
     localStorage.setItem("heychat_kick_channel", kickChannel);
-  }, [twitchChannel, youtubeVideoId, kickChannel, isSidebarOpen, favoritesInput, twitchUser, twitchToken, youtubeUser, youtubeToken, kickUser, kickToken]);
+  }, [twitchChannel, youtubeVideoId, youtubeChatMode, youtubeSendMethod, kickChannel, isSidebarOpen, favoritesInput, twitchUser, twitchToken, youtubeUser, youtubeToken, kickUser, kickToken]);
 
   // Setup Event Listeners
   useEffect(() => {
@@ -144,6 +161,35 @@ function AppContent() {
           }); 
       }));
 
+      // 1b. Moderation: drop deleted messages, messages from banned/timed out users, cleared chats
+      unlisteners.push(listen<ModerationEvent>("chat-moderation", (event) => {
+          const { source, action } = event.payload;
+          const sameSource = (m: ChatMessage) => m.platform === source.platform && m.source?.channel === source.channel;
+          // Prefer the stable id; localised display names don't always match the login in CLEARCHAT
+          const sameUser = (m: ChatMessage, username: string, userId?: string) =>
+              userId && m.user_id
+                  ? m.user_id === userId
+                  : (m.login ?? m.username).toLowerCase() === username.toLowerCase();
+          setMessages((prev) => {
+              switch (action.type) {
+                  case 'message_deleted':
+                      return prev.filter(m => m.id !== action.message_id);
+                  case 'user_timed_out':
+                  case 'user_banned':
+                      return prev.filter(m => !(sameSource(m) && sameUser(m, action.username, action.user_id)));
+                  case 'chat_cleared':
+                      return prev.filter(m => !sameSource(m));
+                  default:
+                      return prev;
+              }
+          });
+      }));
+
+      unlisteners.push(listen("twitch-token-invalid", () => {
+          console.warn("Twitch token is no longer valid");
+          addToast("Your Twitch login has expired. Please log in again.", "error", 8000);
+      }));
+
       // Listen for current user state (Mod status)
       unlisteners.push(listen<{ is_mod: boolean, badges: string[] }>("twitch-current-user-state", (event) => {
           console.log("Twitch User State:", event.payload);
@@ -168,6 +214,28 @@ function AppContent() {
          setYoutubeConnected(true);
       }));
           
+      unlisteners.push(listen<{ video_id: string, reason: 'ended' | 'error' }>("youtube-ended", (event) => {
+          console.log("YouTube chat ended:", event.payload);
+          setYoutubeConnected(false);
+          setYoutubeReplay(null);
+          if (event.payload.reason === 'error') {
+              addToast("Lost connection to YouTube chat", "error", 5000);
+          } else {
+              addToast("YouTube stream has ended", "info", 5000);
+          }
+      }));
+
+      unlisteners.push(listen<YouTubeReplayState>("youtube-replay-state", (event) => {
+          setYoutubeReplay(event.payload);
+      }));
+
+      unlisteners.push(listen<{ video_id: string, requested: YouTubeChatMode, mode: YouTubeChatMode }>("youtube-chat-mode", (event) => {
+          console.log("YouTube chat mode:", event.payload);
+          if (event.payload.mode !== event.payload.requested) {
+              addToast(`YouTube ${event.payload.requested} chat unavailable, showing ${event.payload.mode} chat`, "info", 5000);
+          }
+      }));
+          
       // 3b. Kick Connection Info
       unlisteners.push(listen<string>("kick-connected", (event) => {
           console.log("Kick connected:", event.payload);
@@ -181,6 +249,16 @@ function AppContent() {
           addToast(`Kick Error: ${event.payload}`, "error");
       }));
 
+      unlisteners.push(listen<{ attempt: number, delay_ms: number }>("kick-reconnecting", (event) => {
+          console.warn("Kick reconnecting:", event.payload);
+          addToast(`Kick connection lost, reconnecting (attempt ${event.payload.attempt})...`, "info");
+      }));
+
+      unlisteners.push(listen<string[]>("kick-reconnected", (event) => {
+          console.log("Kick reconnected:", event.payload);
+          addToast("Reconnected to Kick", "success");
+      }));
+
       // 4. Twitch Error (Auth Failure)
       unlisteners.push(listen<string>("twitch-error", (event) => {
           console.error("Twitch Error:", event.payload);
@@ -280,10 +358,19 @@ function AppContent() {
 
   async function connectYoutube() {
     if (!youtubeVideoId) return;
-    await invoke("join_youtube", { videoId: youtubeVideoId });
+    // VOD links like ...?v=ID&t=90s start the chat replay at that point
+    const startSeconds = youtubeVideoId.match(/[?&]t=(\d+)s?/)?.[1];
+    const replay = startSeconds ? { start_offset_ms: Number(startSeconds) * 1000 } : null;
+    await invoke("join_youtube", { videoId: youtubeVideoId, mode: youtubeChatMode, replay });
     setYoutubeConnected(true);
   }
 
+  function controlYoutubeReplay(command: YouTubeReplayCommand) {
+    if (!youtubeReplay) return;
+    invoke("control_youtube_replay", { videoId: youtubeReplay.video_id, command })
+      .catch(e => addToast(`Replay control failed: ${errorMessage(e)}`, "error"));
+  }
+
   async function fetchKickChannelInfo(channel_slug: string) {
       const res = await fetch(`https://kick.com/api/v2/channels/${channel_slug}`);
       if (!res.ok) throw new Error(`Kick API V2 Error: ${res.status}`);
@@ -312,7 +399,7 @@ function AppContent() {
           });
       } catch (e) {
           console.error("Failed to connect Kick:", e);
-          addToast("Kick Connection Failed: " + String(e), 'error');
+          addToast("Kick Connection Failed: " + errorMessage(e), 'error');
       }
   }
 
@@ -332,7 +419,7 @@ function AppContent() {
                is_member: false,
                timestamp: new Date().toISOString(),
                emotes: [],
-               msg_type: 'chat',
+               msg_type: { type: 'chat' },
                system_message: undefined
            };
            setMessages(prev => [...prev.slice(-200), tempMessage]);
@@ -341,7 +428,7 @@ function AppContent() {
                await invoke("send_twitch_message", { channel: twitchChannel, message });
            } catch (e) {
                console.error("Failed to send Twitch message:", e);
-               addToast("Failed to send Twitch message: " + String(e), 'error');
+               addToast("Failed to send Twitch message: " + errorMessage(e), 'error');
            }
 
        } else if (chatProvider === 'kick' && kickConnected && kickToken) {
@@ -360,7 +447,7 @@ function AppContent() {
                is_member: false,
                timestamp: new Date().toISOString(),
                emotes: [],
-               msg_type: 'chat',
+               msg_type: { type: 'chat' },
                system_message: undefined
            };
            setMessages(prev => [...prev.slice(-200), tempMessage]);
@@ -369,11 +456,18 @@ function AppContent() {
                await invoke("send_kick_message", { channel: kickChannel, message, token: kickToken });
            } catch (e) {
                console.error("Failed to send Kick message:", e);
-               addToast("Failed to send Kick message: " + String(e), 'error');
+               addToast("Failed to send Kick message: " + errorMessage(e), 'error');
            }
        } else if (chatProvider === 'youtube' && youtubeConnected && youtubeToken) {
-           addToast('YouTube sending is temporarily disabled due to API 404 errors.', 'info');
-           return;
+           // No local echo: our own message comes back through the chat poller
+           try {
+               // The cookies are a whole Google session; only the web chat path gets them
+               const cookies = youtubeSendMethod === "innertube" ? youtubeCookies : undefined;
+               await invoke("send_youtube_message", { videoId: youtubeVideoId, message, token: youtubeToken, method: youtubeSendMethod, cookies });
+           } catch (e) {
+               console.error("Failed to send YouTube message:", e);
+               addToast("Failed to send YouTube message: " + errorMessage(e), 'error');
+           }
       }
   }
   
@@ -391,42 +485,53 @@ function AppContent() {
       else if (canSendKick) setChatProvider('kick');
       else if (canSendYoutube) setChatProvider('youtube');
   }, [canSendTwitch, canSendYoutube, canSendKick]);
-  const handleUserClick = (username: string) => {
-      setSelectedUser(username);
+  const handleUserClick = (msg: ChatMessage) => {
+      setSelectedMessage(msg);
   };
   
+  // Helix moderation needs ids: the channel the clicked message was sent in and its author
+  const moderationTarget = () => {
+      if (selectedMessage?.platform !== 'Twitch') return null;
+      const broadcasterId = selectedMessage.source?.room_id;
+      const userId = selectedMessage.user_id;
+      return broadcasterId && userId ? { broadcasterId, userId } : null;
+  };
+
   const handleTimeout = async (duration: number) => {
       if (!selectedUser) return;
-      // We need broadcasterId and moderatorId logic or assumption
+      const target = moderationTarget();
+      if (!target) {
+          addToast(`Can't moderate ${selectedUser}: not a Twitch message with a channel and user id`, 'error');
+          return;
+      }
+      const { broadcasterId, userId } = target;
       try {
           await invoke('twitch_ban_user', {
-              broadcasterId: 'BROADCASTER_ID_placeholder', // TODO: Get from State
-              moderatorId: 'MOD_ID_placeholder', // TODO: Get from State
-              userId: 'TARGET_ID', // We need to resolve name to ID
+              broadcasterId,
+              userId,
               reason: 'Timeout via HeyChat',
               duration
           });
-          // For now, since we lack IDs in frontend state easily without fetching:
-          // We can use the chat command fallback or implement IDfetching.
-          // BUT the user instructions implied we should support this.
-          // Let's defer actual implementation or try to use `send_twitch_message` with `/timeout` command as a fallback which is easier?
-          // The request was "quick actions (if the user is a mod of the channel) to timeout or ban".
-          // Using IRC commands is much easier than Helix for this specific case if we are connected as Mod.
-          await invoke('send_twitch_message', { channel: twitchChannel, message: `/timeout ${selectedUser} ${duration}` });
           addToast(`Timed out ${selectedUser} for ${duration}s`, 'success');
       } catch(e) {
-          addToast("Failed to timeout: " + String(e), 'error');
+          addToast("Failed to timeout: " + errorMessage(e), 'error');
       }
   };
 
   const handleBan = async () => {
       if (!selectedUser) return;
+      const target = moderationTarget();
+      if (!target) {
+          addToast(`Can't moderate ${selectedUser}: not a Twitch message with a channel and user id`, 'error');
+          return;
+      }
+      const { broadcasterId, userId } = target;
       try {
-           await invoke('send_twitch_message', { channel: twitchChannel, message: `/ban ${selectedUser}` });
+           await invoke('twitch_ban_user', { broadcasterId, userId, reason: 'Banned via HeyChat' });
            addToast(`Banned ${selectedUser}`, 'success');
-           setSelectedUser(null);
+           setSelectedMessage(null);
       } catch(e) {
-          addToast("Failed to ban: " + String(e), 'error');
+          addToast("Failed to ban: " + errorMessage(e), 'error');
       }
   };
 
@@ -523,13 +628,42 @@ function AppContent() {
                   onChange={(e) => setYoutubeVideoId(e.target.value)}
                   disabled={youtubeConnected}
                 />
+                <select
+                  value={youtubeChatMode}
+                  onChange={(e) => setYoutubeChatMode(e.target.value as YouTubeChatMode)}
+                  disabled={youtubeConnected}
+                  title="Top chat hides potential spam; Live chat shows every message"
+                >
+                  <option value="live">Live chat</option>
+                  <option value="top">Top chat</option>
+                </select>
+                {youtubeUser && (
+                  <select
+                    value={youtubeSendMethod}
+                    onChange={(e) => setYoutubeSendMethod(e.target.value as YouTubeSendMethod)}
+                    title="Data API is the official route but uses daily quota per message; Web chat sends like the YouTube site and has no quota"
+                  >
+                    <option value="data_api">Data API</option>
+                    <option value="innertube">Web chat</option>
+                  </select>
+                )}
+                {youtubeUser && youtubeSendMethod === "innertube" && (
+                  <input
+                    type="password"
+                    value={youtubeCookies}
+                    onChange={(e) => setYoutubeCookies(e.target.value)}
+                    placeholder="youtube.com cookies"
+                    title="Web chat signs in with your youtube.com session: paste the Cookie header of a signed-in youtube.com request"
+                  />
+                )}
                 {!youtubeConnected ? (
                   <button onClick={connectYoutube} className="action-btn">Connect</button>
                 ) : (
                   <button 
                     onClick={async () => {
                         setYoutubeConnected(false);
-                        invoke("leave_youtube").catch(e => console.error("Failed to disconnect YouTube:", e));
+                        setYoutubeReplay(null);
+                        invoke("leave_youtube", { videoId: youtubeVideoId }).catch(e => console.error("Failed to disconnect YouTube:", e));
                     }} 
                     className="action-btn disconnect-btn"
                   >
@@ -537,6 +671,23 @@ function AppContent() {
                   </button>
                 )}
             </div>
+            {youtubeConnected && youtubeReplay && (
+              <div className="input-row" style={{ marginTop: '6px' }}>
+                <button
+                  className="action-btn"
+                  onClick={() => controlYoutubeReplay({ type: youtubeReplay.paused ? 'resume' : 'pause' })}
+                >
+                  {youtubeReplay.paused ? 'Resume' : 'Pause'}
+                </button>
+                <select
+                  value={youtubeReplay.speed}
+                  onChange={(e) => controlYoutubeReplay({ type: 'set_speed', speed: Number(e.target.value) })}
+                  title="Replay speed"
+                >
+                  {[0.5, 1, 1.5, 2, 4].map(speed => <option key={speed} value={speed}>{speed}x</option>)}
+                </select>
+              </div>
+            )}
           </div>
 
           <div className="connection-group">
@@ -653,12 +804,12 @@ function AppContent() {
             <TwitchUserCard 
                 username={selectedUser} 
                 isOpen={!!selectedUser} 
-                onClose={() => setSelectedUser(null)} 
+                onClose={() => setSelectedMessage(null)} 
                 messages={messages}
                 onTimeout={handleTimeout}
                 onBan={handleBan}
                 isMod={isCurrentUserMod} 
-                broadcasterId={broadcasterId}
+                broadcasterId={selectedMessage?.source?.room_id ?? broadcasterId}
                 thirdPartyEmotes={thirdPartyEmotes}
             />
         )}
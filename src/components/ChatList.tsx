@@ -9,7 +9,7 @@ interface Props {
     favorites: string[];
     highlightTerms: string[];
     thirdPartyEmotes?: EmoteMap;
-    onUserClick?: (username: string) => void;
+    onUserClick?: (msg: ChatMessage) => void;
 }
 
 export const ChatList: React.FC<Props> = ({ messages, favorites, highlightTerms, thirdPartyEmotes, onUserClick }) => {
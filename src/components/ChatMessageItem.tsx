@@ -1,29 +1,39 @@
-import { ChatMessage } from "../types";
+import { ChatMessage, isEventMessage } from "../types";
 import { Star } from "lucide-react"; 
 import { useChatSettings } from "../context/ChatSettingsContext";
 
 import { renderMessageWithEmotes } from "../utils/chatRenderer";
 
+// Position in a replayed video, e.g. 1:02:03 or 4:05
+const formatVideoOffset = (ms: number) => {
+  const total = Math.floor(ms / 1000);
+  const h = Math.floor(total / 3600);
+  const m = Math.floor((total % 3600) / 60);
+  const s = String(total % 60).padStart(2, '0');
+  return h > 0 ? `${h}:${String(m).padStart(2, '0')}:${s}` : `${m}:${s}`;
+};
+
 interface Props {
   msg: ChatMessage;
   isFavorite: boolean;
   highlightTerms: string[];
   thirdPartyEmotes?: Map<string, string>;
-  onUserClick?: (username: string) => void;
+  onUserClick?: (msg: ChatMessage) => void;
 }
 
 export const ChatMessageItem = ({ msg, isFavorite, highlightTerms, thirdPartyEmotes, onUserClick }: Props) => {
   const { settings } = useChatSettings();
   const isMod = msg.is_mod;
   const isVip = msg.is_vip;
+  const isEvent = isEventMessage(msg);
 
 
   let messageClass = "chat-message-item";
   if (isFavorite) messageClass += " favorite-message";
   else if (isMod) messageClass += " mod-message";
-  else if (isVip && msg.msg_type !== 'sub') messageClass += " vip-message";
+  else if (isVip && !isEvent) messageClass += " vip-message";
   
-  if (msg.msg_type === 'sub') {
+  if (isEvent) {
       messageClass += " sub-message";
   }
 
@@ -43,7 +53,9 @@ export const ChatMessageItem = ({ msg, isFavorite, highlightTerms, thirdPartyEmo
       <div className="message-meta">
         {settings.showTimestamp && (
             <span className="timestamp" style={{ marginRight: '6px', opacity: 0.5, fontSize: '0.9em' }}>
-                {new Date(msg.timestamp).toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' })}
+                {msg.video_offset_ms !== undefined && msg.video_offset_ms !== null
+                    ? formatVideoOffset(msg.video_offset_ms)
+                    : new Date(msg.timestamp).toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' })}
             </span>
         )}
 
@@ -54,7 +66,10 @@ export const ChatMessageItem = ({ msg, isFavorite, highlightTerms, thirdPartyEmo
                 {isFavorite && <Star size={14} className="favorite-badge" fill="currentColor" />}
                 {isMod && <span className="mod-badge-text">MOD</span>}
                 {isVip && <span className="vip-badge-text">VIP</span>}
-                {msg.is_member && <span className="member-badge-text">MEMBER</span>}
+                {msg.platform === "YouTube" && msg.badges.includes("owner") && <span className="mod-badge-text">OWNER</span>}
+                {msg.is_member && (msg.badge_images?.member
+                    ? <img className="member-badge" src={msg.badge_images.member} alt="Member" style={{ height: '14px' }} />
+                    : <span className="member-badge-text">MEMBER</span>)}
                 
                 {/* Platform Icon */}
                 {msg.platform === "Twitch" ? (
@@ -81,7 +96,7 @@ export const ChatMessageItem = ({ msg, isFavorite, highlightTerms, thirdPartyEmo
         
         <span 
             className="username" 
-            onClick={() => onUserClick?.(msg.username)}
+            onClick={() => onUserClick?.(msg)}
             style={{ 
                 color: settings.usernameColor === 'static' 
                     ? settings.staticUsernameColor 
@@ -94,12 +109,16 @@ export const ChatMessageItem = ({ msg, isFavorite, highlightTerms, thirdPartyEmo
         <span className="separator">:</span>
       </div>
       
-      {msg.msg_type === 'sub' && msg.system_message && (
+      {isEvent && msg.system_message && (
           <div className="system-text" style={{ fontSize: '0.85em', fontStyle: 'italic', marginBottom: '2px', color: '#a970ff' }}>
               {msg.system_message}
           </div>
       )}
 
+      {msg.msg_type?.type === 'super_sticker' && msg.msg_type.sticker_url && (
+          <img className="super-sticker" src={msg.msg_type.sticker_url} alt={msg.msg_type.sticker_label || 'Super Sticker'} style={{ height: '72px', display: 'block' }} />
+      )}
+
       {/* Only render message text if it exists (for subs it might be empty if they didn't type anything) */}
       {msg.message && (
           <div className="text" style={{ wordBreak: "break-word" }}>
@@ -2,6 +2,7 @@ import { useState, useEffect, useMemo } from 'react';
 import { Search, X, Loader2, Lock } from 'lucide-react';
 import { invoke } from '@tauri-apps/api/core';
 import { EmoteData } from '../utils/emotes';
+import { errorMessage } from '../utils/errors';
 
 interface TwitchEmote {
     id: string;
@@ -40,7 +41,7 @@ export const EmotePicker = ({ isOpen, onClose, onSelect, thirdPartyEmotes, broad
                 })
                 .catch(err => {
                     console.error("Failed to fetch twitch emotes:", err);
-                    setError(String(err)); 
+                    setError(errorMessage(err)); 
                 })
                 .finally(() => {
                     setLoadingTwitch(false);
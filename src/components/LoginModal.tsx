@@ -1,5 +1,6 @@
 import { X, ExternalLink, LogOut } from 'lucide-react';
 import { invoke } from '@tauri-apps/api/core';
+import { errorMessage } from '../utils/errors';
 
 interface LoginModalProps {
   isOpen: boolean;
@@ -23,7 +24,7 @@ export function LoginModal({ isOpen, onClose, twitchUser, youtubeUser, kickUser,
           await invoke('start_twitch_oauth');
       } catch (e) {
           console.error("Failed to start Twitch OAuth:", e);
-          alert("Failed to open browser: " + String(e));
+          alert("Failed to open browser: " + errorMessage(e));
       }
   };
 
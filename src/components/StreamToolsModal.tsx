@@ -1,6 +1,7 @@
 import { useState } from 'react';
 import { X, PieChart, Trophy, Plus, Trash2 } from 'lucide-react';
 import { invoke } from '@tauri-apps/api/core';
+import { errorMessage } from '../utils/errors';
 
 interface Props {
   isOpen: boolean;
@@ -54,8 +55,8 @@ export const StreamToolsModal = ({ isOpen, onClose, broadcasterId }: Props) => {
           });
           setSuccess('Poll created successfully!');
           setTimeout(() => { onClose(); resetForm(); }, 1500);
-      } catch (err: any) {
-          setError(err.toString());
+      } catch (err) {
+          setError(errorMessage(err));
       } finally {
           setLoading(false);
       }
@@ -77,8 +78,8 @@ export const StreamToolsModal = ({ isOpen, onClose, broadcasterId }: Props) => {
           });
           setSuccess('Prediction created successfully!');
           setTimeout(() => { onClose(); resetForm(); }, 1500);
-      } catch (err: any) {
-          setError(err.toString());
+      } catch (err) {
+          setError(errorMessage(err));
       } finally {
           setLoading(false);
       }
@@ -1,22 +1,112 @@
 export interface Emote {
     id: string;
     code: string;
-    start: number;
-    end: number;
+    start: number; // Code point offset into the message
+    end: number; // Exclusive
 }
 
+export interface ChatSource {
+    platform: 'Twitch' | 'YouTube' | 'Kick';
+    channel: string;
+    room_id?: string;
+    display_name?: string;
+}
+
+export type MessageType =
+    | { type: 'chat' }
+    | { type: 'action' }
+    | { type: 'sub'; tier?: string }
+    | { type: 'resub'; tier?: string; months: number; streak_months?: number }
+    | { type: 'sub_gift'; tier?: string; count: number; recipient?: string; gifted_months?: number; gifter_total?: number; anonymous: boolean }
+    | { type: 'gift_paid_upgrade'; gifter?: string }
+    | { type: 'raid'; viewer_count: number }
+    | { type: 'bits_badge_tier'; threshold: number }
+    | { type: 'ritual'; name: string }
+    | { type: 'cheer'; bits: number }
+    | { type: 'redemption'; reward_id: string }
+    | { type: 'announcement'; color?: string }
+    | { type: 'super_chat'; amount: string; currency?: string; header_color?: string; body_color?: string; text_color?: string }
+    | { type: 'super_sticker'; amount: string; currency?: string; sticker_url?: string; sticker_label?: string; color?: string }
+    | { type: 'membership'; level?: string; months?: number }
+    | { type: 'membership_gift'; count: number }
+    | { type: 'system' };
+
+// Everything except plain chat lines gets the highlighted "event" styling
+export const isEventMessage = (msg: { msg_type?: MessageType }) =>
+    !!msg.msg_type && msg.msg_type.type !== 'chat' && msg.msg_type.type !== 'action';
+
+// Top chat is YouTube's filtered feed, Live chat is every message
+export type YouTubeChatMode = 'top' | 'live';
+
+// 'data_api' uses YouTube Data API quota per message; 'innertube' posts like the web chat does,
+// signed in with the user's youtube.com cookies
+export type YouTubeSendMethod = 'data_api' | 'innertube';
+
 export interface ChatMessage {
     id: string;
     platform: 'Twitch' | 'YouTube' | 'Kick';
-    username: string;
+    source?: ChatSource;
+    username: string; // Display name
+    login?: string; // Twitch login or Kick slug of the sender; absent on YouTube and Kick notices
     message: string;
     color?: string;
     badges: string[];
+    badge_images?: Record<string, string>; // Badge name -> image URL (YouTube membership badges)
     is_mod: boolean;
     is_vip: boolean;
     is_member: boolean;
-    timestamp: string;
+    timestamp: string; // UTC send time reported by the platform
+    received_at?: string;
     emotes?: Emote[];
-    msg_type?: 'chat' | 'sub';
+    msg_type?: MessageType;
     system_message?: string;
+    video_offset_ms?: number; // Set on replayed YouTube chat
+    user_id?: string;
+    avatar_url?: string;
+}
+
+export interface YouTubeReplayState {
+    video_id: string;
+    position_ms: number;
+    speed: number;
+    paused: boolean;
+}
+
+export type YouTubeReplayCommand =
+    | { type: 'pause' }
+    | { type: 'resume' }
+    | { type: 'set_speed'; speed: number }
+    | { type: 'seek'; offset_ms: number };
+
+export type ModerationAction =
+    | { type: 'message_deleted'; message_id: string }
+    | { type: 'user_timed_out'; username: string; user_id?: string; duration_secs: number }
+    | { type: 'user_banned'; username: string; user_id?: string }
+    | { type: 'user_unbanned'; username: string; user_id?: string }
+    | { type: 'chat_cleared' };
+
+export interface ModerationEvent {
+    source: ChatSource;
+    action: ModerationAction;
+    timestamp: string; // UTC, like ChatMessage.timestamp; the receive time where the platform doesn't say
+}
+
+// Error returned by Tauri commands
+export type HeychatErrorKind =
+    | 'not_connected'
+    | 'unauthorized'
+    | 'missing_scope'
+    | 'rate_limited'
+    | 'not_found'
+    | 'platform_error'
+    | 'network'
+    | 'other';
+
+export interface HeychatError {
+    kind: HeychatErrorKind;
+    message: string;
+    scope?: string; // missing_scope
+    retry_after?: number | null; // rate_limited, seconds
+    status?: number; // platform_error
+    body?: string; // platform_error
 }
@@ -3,7 +3,7 @@ import { Emote } from "../types";
 
 // Helper to replace text with emote images AND highlight mentions
 export const renderMessageWithEmotes = (text: string, emotes?: Emote[], highlightTerms?: string[], thirdPartyEmotes?: Map<string, string>) => {
-    // 1. Split by emotes first (highest priority, Twitch/YT native)
+    // 1. Split by emotes first (highest priority, Twitch/YT/Kick native)
     
     // Sort native emotes
     const sortedEmotes = emotes ? [...emotes].sort((a, b) => a.start - b.start) : [];
@@ -14,10 +14,12 @@ export const renderMessageWithEmotes = (text: string, emotes?: Emote[], highligh
     if (sortedEmotes.length === 0) {
         processedNodeParts = [text];
     } else {
+        // Emote ranges count code points with an exclusive end, so slice by code point, not UTF-16 unit
+        const chars = Array.from(text);
         let lastIndex = 0;
         sortedEmotes.forEach(emote => {
             if (emote.start > lastIndex) {
-                 processedNodeParts.push(text.substring(lastIndex, emote.start));
+                 processedNodeParts.push(chars.slice(lastIndex, emote.start).join(''));
             }
             const url = emote.id.startsWith('http') 
                 ? emote.id 
@@ -25,50 +27,14 @@ export const renderMessageWithEmotes = (text: string, emotes?: Emote[], highligh
             processedNodeParts.push(
                 <img key={`emote-native-${emote.id}-${emote.start}`} src={url} alt={emote.code} title={emote.code} className="chat-emote" />
             );
-            lastIndex = emote.end + 1;
+            lastIndex = emote.end;
         });
-        if (lastIndex < text.length) {
-            processedNodeParts.push(text.substring(lastIndex));
+        if (lastIndex < chars.length) {
+            processedNodeParts.push(chars.slice(lastIndex).join(''));
         }
     }
 
-    // 2. Process Kick Emotes (String replacement in text nodes)
-    // Format: [emote:ID:Name] - Name allows alphanumeric, dashes, underscores
-    const kickEmoteRegex = /\[emote:(\d+):([\w\-]+)\]/g;
-    
-    // We need to process existing nodes to find these strings
-    let kickProcessedParts: React.ReactNode[] = [];
-    processedNodeParts.forEach(part => {
-        if (typeof part === 'string') {
-            const split = part.split(kickEmoteRegex);
-            // Split will be: ["text before", "ID", "Name", "text after", ...]
-            for (let i = 0; i < split.length; i += 3) {
-                // 1. Text part
-                if (split[i]) kickProcessedParts.push(split[i]);
-                
-                // 2. Emote part (if exists)
-                if (i + 1 < split.length) {
-                    const id = split[i+1];
-                    const name = split[i+2];
-                    const url = `https://files.kick.com/emotes/${id}/fullsize`;
-                    kickProcessedParts.push(
-                        <img 
-                            key={`emote-kick-${id}-${i}`} 
-                            src={url} 
-                            alt={name} 
-                            title={name} 
-                            className="chat-emote" 
-                        />
-                    );
-                }
-            }
-        } else {
-            kickProcessedParts.push(part);
-        }
-    });
-    processedNodeParts = kickProcessedParts;
-
-    // 3. Process 3rd Party Emotes (String replacement in text nodes)
+    // 2. Process 3rd Party Emotes (String replacement in text nodes)
     // Only if we have them
     if (thirdPartyEmotes && thirdPartyEmotes.size > 0) {
         const nextParts: React.ReactNode[] = [];
@@ -99,7 +65,7 @@ export const renderMessageWithEmotes = (text: string, emotes?: Emote[], highligh
         processedNodeParts = nextParts;
     }
 
-    // 4. Process Mentions (String replacement in text nodes)
+    // 3. Process Mentions (String replacement in text nodes)
     const cleanHighlightTerms = (highlightTerms || [])
         .map(t => t.trim().replace(/^@/, '').toLowerCase())
         .filter(t => t.length > 0);
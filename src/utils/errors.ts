@@ -0,0 +1,12 @@
+import { HeychatError } from '../types';
+
+export function isHeychatError(e: unknown): e is HeychatError {
+    return typeof e === 'object' && e !== null && 'kind' in e && 'message' in e;
+}
+
+// Readable text for anything thrown by invoke() or fetch()
+export function errorMessage(e: unknown): string {
+    if (isHeychatError(e)) return e.message;
+    if (e instanceof Error) return e.message;
+    return String(e);
+}